@@ -96,7 +96,7 @@ pub fn connect() -> impl Stream<Item = Event> {
                             log::debug!("Config created or modified");
 
                             // store update config
-                            let config = app_config;
+                            let config = *app_config;
 
                             // send the updated config to the sim monitor
                             if let Some(ref mut connection) = sim_monitor_connection {
@@ -0,0 +1,74 @@
+use crate::iracing_client::{playback_speed, IbtConfig, ReplayConfig, ScenarioConfig, SimSource};
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// Command line flags, for development. These override the corresponding settings in the config
+// file, but are never written back to it.
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub sim_source: Option<SimSource>,
+}
+
+pub fn args() -> &'static CliArgs {
+    static ARGS: OnceLock<CliArgs> = OnceLock::new();
+    ARGS.get_or_init(|| {
+        let args = parse(std::env::args().skip(1));
+        log::debug!("Command line arguments: {:?}", args);
+        args
+    })
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> CliArgs {
+    let mut replay_files = Vec::new();
+    let mut scenario_file = None;
+    let mut ibt_file = None;
+    let mut speed = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // --replay <file>, can be given multiple times
+            "--replay" => match args.next() {
+                Some(file) => replay_files.push(PathBuf::from(file)),
+                None => log::warn!("Missing file argument for --replay"),
+            },
+            // --scenario <file>
+            "--scenario" => match args.next() {
+                Some(file) => scenario_file = Some(PathBuf::from(file)),
+                None => log::warn!("Missing file argument for --scenario"),
+            },
+            // --ibt <file>
+            "--ibt" => match args.next() {
+                Some(file) => ibt_file = Some(PathBuf::from(file)),
+                None => log::warn!("Missing file argument for --ibt"),
+            },
+            // --speed <factor>, playback speed of the scenario
+            "--speed" => match args.next().map(|speed| speed.parse::<f64>()) {
+                Some(Ok(value)) => speed = Some(playback_speed(value)),
+                _ => log::warn!("Missing or invalid argument for --speed"),
+            },
+            unknown => log::warn!("Ignoring unknown argument: {unknown}"),
+        }
+    }
+
+    if speed.is_some() && (scenario_file.is_none() || ibt_file.is_some()) {
+        log::warn!("Ignoring --speed, it only applies to --scenario");
+    }
+
+    let mut cli_args = CliArgs::default();
+    if let Some(file) = ibt_file {
+        cli_args.sim_source = Some(SimSource::Ibt(IbtConfig { file }));
+    } else if let Some(file) = scenario_file {
+        let default = ScenarioConfig::default();
+        cli_args.sim_source = Some(SimSource::Scenario(ScenarioConfig {
+            file,
+            speed: speed.unwrap_or(default.speed),
+        }));
+    } else if !replay_files.is_empty() {
+        cli_args.sim_source = Some(SimSource::Replay(ReplayConfig {
+            files: replay_files,
+            ..Default::default()
+        }));
+    }
+    cli_args
+}
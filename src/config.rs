@@ -13,7 +13,9 @@ use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::sim_monitor::MqttConfig;
+use crate::iracing_client::SimSource;
+use crate::sim_monitor::{DiscoveryConfig, MqttConfig};
+use crate::units::UnitSystem;
 
 #[derive(Debug)]
 enum ConfigError {
@@ -27,9 +29,13 @@ enum ConfigError {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct AppConfig {
     pub mqtt: MqttConfig,
     pub mqtt_enabled: bool,
+    pub discovery: DiscoveryConfig,
+    pub unit_system: UnitSystem,
+    pub sim_source: SimSource,
 }
 
 impl AppConfig {
@@ -224,8 +230,9 @@ fn load() -> Result<Config, ConfigError> {
 
 #[derive(Debug, Clone)]
 pub enum Event {
-    Created(AppConfig),
-    Modified(AppConfig),
+    // Boxed, the config is much larger than a path
+    Created(Box<AppConfig>),
+    Modified(Box<AppConfig>),
     Deleted(PathBuf),
 }
 
@@ -245,7 +252,7 @@ pub fn watch() -> impl Stream<Item = Event> {
                         Create(_) => {
                             log::debug!("Config file created");
                             match get_app_config_with_error() {
-                                Ok(app_config) => Some(Event::Created(app_config)),
+                                Ok(app_config) => Some(Event::Created(Box::new(app_config))),
                                 Err(e) => {
                                     log::warn!("Failed to get app config: {:?}", e);
                                     None
@@ -255,7 +262,7 @@ pub fn watch() -> impl Stream<Item = Event> {
                         Modify(_) => {
                             log::debug!("Config file modified");
                             match get_app_config_with_error() {
-                                Ok(app_config) => Some(Event::Modified(app_config)),
+                                Ok(app_config) => Some(Event::Modified(Box::new(app_config))),
                                 Err(e) => {
                                     log::warn!("Failed to get app config: {:?}", e);
                                     None
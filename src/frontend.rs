@@ -160,8 +160,8 @@ impl IracingMonitorGui {
                             }
                             sim_monitor::Event::ConnectedToSim(state)
                             | sim_monitor::Event::DisconnectedFromSim(state) => {
-                                self.tray_icon.update_state(state.clone());
-                                self.sim_state = Some(state);
+                                self.tray_icon.update_state((*state).clone());
+                                self.sim_state = Some(*state);
                             }
                         }
                     }
@@ -1,11 +1,27 @@
 pub use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+mod flags;
+mod ibt;
+mod memory_map;
+mod proton;
+mod replay;
+mod scenario;
+mod snapshot;
+pub use flags::{Flag, FLAG_PRIORITY};
+pub use ibt::{IbtClient, IbtConfig};
+#[cfg(not(target_os = "windows"))]
+pub use proton::ProtonClient;
+pub use proton::ProtonConfig;
+pub use replay::{ReplayClient, ReplayConfig};
+pub use scenario::{playback_speed, ScenarioClient, ScenarioConfig};
+pub use snapshot::{SimSnapshot, Telemetry, TelemetryValue};
 
 #[async_trait]
 pub trait SimClient {
-    fn new() -> Self;
     // async fn connect(&mut self) -> bool;
     // fn is_connected(&self) -> bool;
-    async fn get_current_session_type(&mut self) -> Option<String>;
+    async fn get_snapshot(&mut self) -> SimSnapshot;
 }
 
 #[cfg(target_os = "windows")]
@@ -17,3 +33,56 @@ pub use windows::IracingClient as Client;
 mod mock;
 #[cfg(not(target_os = "windows"))]
 pub use mock::MockClient as Client;
+
+/// Where the sim data comes from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimSource {
+    /// The running sim (a mock client on platforms where iRacing can't run)
+    #[default]
+    Live,
+    /// Recorded session info files, see [`ReplayConfig`]
+    Replay(ReplayConfig),
+    /// A scripted timeline of sim states, see [`ScenarioConfig`]
+    Scenario(ScenarioConfig),
+    /// An .ibt telemetry file, see [`IbtConfig`]
+    Ibt(IbtConfig),
+    /// iRacing running under Steam Proton on Linux, see [`ProtonConfig`]. Falls back to the
+    /// live client on Windows.
+    Proton(ProtonConfig),
+}
+
+pub fn create_client(source: &SimSource) -> Box<dyn SimClient + Send> {
+    match source {
+        SimSource::Live => Box::new(Client::new()),
+        SimSource::Replay(config) => match ReplayClient::new(config) {
+            Ok(client) => Box::new(client),
+            Err(e) => {
+                log::error!("Failed to set up replay client ({e:?}), using live client");
+                Box::new(Client::new())
+            }
+        },
+        SimSource::Scenario(config) => match ScenarioClient::new(config) {
+            Ok(client) => Box::new(client),
+            Err(e) => {
+                log::error!("Failed to set up scenario client ({e:?}), using live client");
+                Box::new(Client::new())
+            }
+        },
+        SimSource::Ibt(config) => match IbtClient::new(config) {
+            Ok(client) => Box::new(client),
+            Err(e) => {
+                log::error!("Failed to set up .ibt client ({e:?}), using live client");
+                Box::new(Client::new())
+            }
+        },
+        #[cfg(not(target_os = "windows"))]
+        SimSource::Proton(config) => Box::new(ProtonClient::new(config)),
+        // The native client already reads the memory map, there is no Wine prefix to bridge
+        #[cfg(target_os = "windows")]
+        SimSource::Proton(_) => {
+            log::error!("The Proton source only works on Linux, using live client");
+            Box::new(Client::new())
+        }
+    }
+}
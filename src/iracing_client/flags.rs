@@ -0,0 +1,168 @@
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+/// A single flag from the SessionFlags bitfield (irsdk_Flags)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Flag {
+    // Global flags
+    Checkered,
+    White,
+    Green,
+    Yellow,
+    Red,
+    Blue,
+    Debris,
+    Crossed,
+    YellowWaving,
+    OneLapToGreen,
+    GreenHeld,
+    TenToGo,
+    FiveToGo,
+    RandomWaving,
+    Caution,
+    CautionWaving,
+    // Driver black flags
+    Black,
+    Disqualify,
+    Servicible,
+    Furled,
+    Repair,
+    // Start lights
+    StartHidden,
+    StartReady,
+    StartSet,
+    StartGo,
+}
+
+impl Flag {
+    pub fn bit(&self) -> u32 {
+        match self {
+            Flag::Checkered => 0x0000_0001,
+            Flag::White => 0x0000_0002,
+            Flag::Green => 0x0000_0004,
+            Flag::Yellow => 0x0000_0008,
+            Flag::Red => 0x0000_0010,
+            Flag::Blue => 0x0000_0020,
+            Flag::Debris => 0x0000_0040,
+            Flag::Crossed => 0x0000_0080,
+            Flag::YellowWaving => 0x0000_0100,
+            Flag::OneLapToGreen => 0x0000_0200,
+            Flag::GreenHeld => 0x0000_0400,
+            Flag::TenToGo => 0x0000_0800,
+            Flag::FiveToGo => 0x0000_1000,
+            Flag::RandomWaving => 0x0000_2000,
+            Flag::Caution => 0x0000_4000,
+            Flag::CautionWaving => 0x0000_8000,
+            Flag::Black => 0x0001_0000,
+            Flag::Disqualify => 0x0002_0000,
+            Flag::Servicible => 0x0004_0000,
+            Flag::Furled => 0x0008_0000,
+            Flag::Repair => 0x0010_0000,
+            Flag::StartHidden => 0x1000_0000,
+            Flag::StartReady => 0x2000_0000,
+            Flag::StartSet => 0x4000_0000,
+            Flag::StartGo => 0x8000_0000,
+        }
+    }
+}
+
+impl Display for Flag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Flag::Checkered => write!(f, "Checkered"),
+            Flag::White => write!(f, "White"),
+            Flag::Green => write!(f, "Green"),
+            Flag::Yellow => write!(f, "Yellow"),
+            Flag::Red => write!(f, "Red"),
+            Flag::Blue => write!(f, "Blue"),
+            Flag::Debris => write!(f, "Debris"),
+            Flag::Crossed => write!(f, "Crossed"),
+            Flag::YellowWaving => write!(f, "Yellow waving"),
+            Flag::OneLapToGreen => write!(f, "One lap to green"),
+            Flag::GreenHeld => write!(f, "Green held"),
+            Flag::TenToGo => write!(f, "Ten to go"),
+            Flag::FiveToGo => write!(f, "Five to go"),
+            Flag::RandomWaving => write!(f, "Random waving"),
+            Flag::Caution => write!(f, "Caution"),
+            Flag::CautionWaving => write!(f, "Caution waving"),
+            Flag::Black => write!(f, "Black"),
+            Flag::Disqualify => write!(f, "Disqualify"),
+            Flag::Servicible => write!(f, "Servicible"),
+            Flag::Furled => write!(f, "Furled"),
+            // The black flag with an orange circle
+            Flag::Repair => write!(f, "Meatball"),
+            Flag::StartHidden => write!(f, "Start hidden"),
+            Flag::StartReady => write!(f, "Start ready"),
+            Flag::StartSet => write!(f, "Start set"),
+            Flag::StartGo => write!(f, "Start go"),
+        }
+    }
+}
+
+// Serialized as the display name, like the other enums published to HA
+impl Serialize for Flag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// Most important first, when several flags are shown at once
+pub const FLAG_PRIORITY: [Flag; 12] = [
+    Flag::Red,
+    Flag::Disqualify,
+    Flag::Black,
+    Flag::Repair,
+    Flag::Checkered,
+    Flag::CautionWaving,
+    Flag::Caution,
+    Flag::YellowWaving,
+    Flag::Yellow,
+    Flag::White,
+    Flag::Blue,
+    Flag::Green,
+];
+
+/// The decoded SessionFlags bitfield
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionFlags(u32);
+
+impl SessionFlags {
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn contains(&self, flag: Flag) -> bool {
+        self.0 & flag.bit() != 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Flag> + '_ {
+        Flag::iter().filter(|flag| self.contains(*flag))
+    }
+
+    /// The flag that matters most right now, like the flag a marshal would wave at you
+    pub fn dominant(&self) -> Option<Flag> {
+        FLAG_PRIORITY.into_iter().find(|flag| self.contains(*flag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_flags() {
+        // Green flag with the start lights on go, while a blue flag is shown to us
+        let flags = SessionFlags::from_bits(0x8000_0024);
+        assert_eq!(
+            flags.iter().collect::<Vec<_>>(),
+            vec![Flag::Green, Flag::Blue, Flag::StartGo]
+        );
+        assert_eq!(flags.dominant(), Some(Flag::Blue));
+
+        let flags = SessionFlags::from_bits(Flag::CautionWaving.bit() | Flag::Yellow.bit());
+        assert_eq!(flags.dominant(), Some(Flag::CautionWaving));
+        assert_eq!(SessionFlags::default().dominant(), None);
+    }
+}
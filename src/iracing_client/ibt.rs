@@ -0,0 +1,71 @@
+use crate::iracing_client::{SimClient, SimSnapshot, Telemetry};
+use crate::irsdk::ibt::IbtFile;
+use crate::session_info::SessionInfo;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct IbtConfig {
+    /// .ibt telemetry file recorded by iRacing
+    pub file: PathBuf,
+}
+
+/// Replays an .ibt telemetry file at the speed it was recorded
+pub struct IbtClient {
+    ibt: IbtFile,
+    session_info: Option<Arc<SessionInfo>>,
+    started: Instant,
+}
+
+impl IbtClient {
+    pub fn new(config: &IbtConfig) -> Result<Self> {
+        let ibt = IbtFile::open(&config.file)?;
+        let session_info = match SessionInfo::from_yaml(&ibt.session_info) {
+            Ok(session_info) => Some(Arc::new(session_info)),
+            Err(e) => {
+                log::warn!("Failed to parse session info from {:?}: {e:?}", config.file);
+                None
+            }
+        };
+        if ibt.num_samples() == 0 {
+            anyhow::bail!("No samples in {:?}", config.file);
+        }
+
+        log::info!(
+            "Replaying {} samples ({} variables) from {:?}",
+            ibt.num_samples(),
+            ibt.vars.len(),
+            config.file
+        );
+        Ok(Self {
+            ibt,
+            session_info,
+            started: Instant::now(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl SimClient for IbtClient {
+    async fn get_snapshot(&mut self) -> SimSnapshot {
+        // Loop the recording
+        let tick_rate = self.ibt.header.tick_rate.max(1) as f64;
+        let index =
+            (self.started.elapsed().as_secs_f64() * tick_rate) as usize % self.ibt.num_samples();
+        let Some(sample) = self.ibt.sample(index) else {
+            return SimSnapshot::default();
+        };
+
+        SimSnapshot {
+            connected: true,
+            session_info: self.session_info.clone(),
+            telemetry: Telemetry::read(&sample),
+            tick_count: sample.read_name::<i32>("SessionTick").unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::iracing_client::{SimSnapshot, Telemetry};
+use crate::irsdk::memory_map::MemoryMap;
+use crate::session_info::SessionInfo;
+
+use std::sync::Arc;
+
+/// Turns the contents of the irsdk memory map into snapshots. Shared by the clients that get
+/// hold of the memory map in different ways.
+#[derive(Default)]
+pub struct MemoryMapReader {
+    memory_map: MemoryMap,
+    session_info: Option<Arc<SessionInfo>>,
+}
+
+impl MemoryMapReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&mut self, data: &[u8]) -> SimSnapshot {
+        let frame = match self.memory_map.read(data) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => {
+                self.session_info = None;
+                return SimSnapshot::default();
+            }
+            Err(e) => {
+                log::warn!("Failed to read the iRacing memory map: {e:?}");
+                return SimSnapshot::default();
+            }
+        };
+
+        // The session info is only parsed again when the sim updates it
+        if let Some(yaml) = &frame.session_info {
+            match SessionInfo::from_yaml(yaml) {
+                Ok(session_info) => self.session_info = Some(Arc::new(session_info)),
+                Err(e) => log::warn!("Failed to parse session info: {e:?}"),
+            }
+        }
+
+        let sample = frame.sample();
+        SimSnapshot {
+            connected: true,
+            session_info: self.session_info.clone(),
+            telemetry: Telemetry::read(&sample),
+            tick_count: sample.read_name::<i32>("SessionTick").unwrap_or_default(),
+        }
+    }
+}
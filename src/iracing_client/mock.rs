@@ -1,10 +1,20 @@
-use crate::iracing_client::SimClient;
+use crate::iracing_client::{SimClient, SimSnapshot, Telemetry};
+use crate::session_info::{Session, SessionInfo, SessionList};
+use std::sync::Arc;
 
 pub struct MockClient {
     connected: bool,
+    tick_count: i32,
 }
 
 impl MockClient {
+    pub fn new() -> Self {
+        Self {
+            connected: false,
+            tick_count: 0,
+        }
+    }
+
     // fn is_connected(&self) -> bool {
     //     self.connected
     // }
@@ -17,19 +27,35 @@ impl MockClient {
 
 #[async_trait::async_trait]
 impl SimClient for MockClient {
-    fn new() -> Self {
-        Self { connected: false }
-    }
-
-    async fn get_current_session_type(&mut self) -> Option<String> {
+    async fn get_snapshot(&mut self) -> SimSnapshot {
         if !self.connect().await {
-            return None;
+            return SimSnapshot::default();
         }
 
-        if self.connected {
-            Some("Practice".to_string()) // Mock implementation
-        } else {
-            None
+        // Mock implementation, a single practice session
+        self.tick_count += 60;
+        let session_info = SessionInfo {
+            session_info: SessionList {
+                sessions: vec![Session {
+                    session_num: 0,
+                    session_type: "Practice".to_string(),
+                    ..Default::default()
+                }],
+            },
+            ..Default::default()
+        };
+
+        SimSnapshot {
+            connected: self.connected,
+            session_info: Some(Arc::new(session_info)),
+            telemetry: Telemetry {
+                session_num: Some(0),
+                session_time: Some(f64::from(self.tick_count) / 60.0),
+                session_state: Some(4),
+                is_on_track: Some(true),
+                ..Default::default()
+            },
+            tick_count: self.tick_count,
         }
     }
 }
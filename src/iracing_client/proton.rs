@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// The client only makes sense on Linux, on Windows the native client reads the memory map
+#[cfg(not(target_os = "windows"))]
+mod client;
+#[cfg(not(target_os = "windows"))]
+pub use client::ProtonClient;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ProtonConfig {
+    /// Copy of the irsdk memory map, exported by a bridge running inside the Proton prefix
+    pub memory_map: PathBuf,
+    /// Only read the memory map while this process is running. Set to an empty string to
+    /// skip the check, e.g. when the memory map comes from somewhere else.
+    pub process_name: String,
+}
+
+impl Default for ProtonConfig {
+    fn default() -> Self {
+        Self {
+            memory_map: PathBuf::from("/dev/shm/IRSDKMemMapFileName"),
+            process_name: "iRacingSim64DX11.exe".to_string(),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use super::ProtonConfig;
+use crate::iracing_client::memory_map::MemoryMapReader;
+use crate::iracing_client::{SimClient, SimSnapshot};
+
+use std::path::{Path, PathBuf};
+
+/// Reads iRacing telemetry on Linux, when the sim runs under Steam Proton
+pub struct ProtonClient {
+    config: ProtonConfig,
+    proc_dir: PathBuf,
+    reader: MemoryMapReader,
+    connected: bool,
+}
+
+impl ProtonClient {
+    pub fn new(config: &ProtonConfig) -> Self {
+        log::info!(
+            "Reading the iRacing memory map from {:?}",
+            config.memory_map
+        );
+        Self {
+            config: config.clone(),
+            proc_dir: PathBuf::from("/proc"),
+            reader: MemoryMapReader::new(),
+            connected: false,
+        }
+    }
+
+    fn is_sim_running(&self) -> bool {
+        self.config.process_name.is_empty()
+            || is_process_running(&self.proc_dir, &self.config.process_name)
+    }
+
+    fn read_snapshot(&mut self) -> SimSnapshot {
+        if !self.is_sim_running() {
+            return SimSnapshot::default();
+        }
+
+        // A torn write by the bridge, while we read the file, is not detected
+        match std::fs::read(&self.config.memory_map) {
+            Ok(data) => self.reader.snapshot(&data),
+            Err(e) => {
+                log::debug!("Failed to read {:?}: {e}", self.config.memory_map);
+                SimSnapshot::default()
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SimClient for ProtonClient {
+    async fn get_snapshot(&mut self) -> SimSnapshot {
+        let snapshot = self.read_snapshot();
+        if snapshot.connected != self.connected {
+            if snapshot.connected {
+                log::info!("Connected to iRacing under Proton");
+            } else {
+                log::info!("Lost connection to iRacing.");
+            }
+            self.connected = snapshot.connected;
+        }
+        snapshot
+    }
+}
+
+/// Look for a process by executable name. Wine processes show up with their Windows path
+/// (`C:\...\iRacingSim64DX11.exe`) in the command line, and `comm` is cut off at 15
+/// characters, so we go by the first command line argument.
+fn is_process_running(proc_dir: &Path, process_name: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(proc_dir) else {
+        return false;
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter_map(|entry| std::fs::read(entry.path().join("cmdline")).ok())
+        .any(|cmdline| {
+            let program = cmdline.split(|&b| b == 0).next().unwrap_or_default();
+            let program = String::from_utf8_lossy(program);
+            program
+                .rsplit(['/', '\\'])
+                .next()
+                .is_some_and(|name| name.eq_ignore_ascii_case(process_name))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irsdk::testing::{build, var};
+    use crate::irsdk::VarType;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_process(proc_dir: &Path, pid: u32, cmdline: &str) {
+        let dir = proc_dir.join(pid.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cmdline"), cmdline).unwrap();
+    }
+
+    #[test]
+    fn find_wine_process() {
+        let proc_dir = temp_dir("proton-proc");
+        write_process(&proc_dir, 1, "/sbin/init\0");
+        assert!(!is_process_running(&proc_dir, "iRacingSim64DX11.exe"));
+
+        write_process(
+            &proc_dir,
+            4242,
+            "C:\\Program Files (x86)\\iRacing\\iRacingSim64DX11.exe\0",
+        );
+        assert!(is_process_running(&proc_dir, "iRacingSim64DX11.exe"));
+        std::fs::remove_dir_all(&proc_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_memory_map_file() {
+        let dir = temp_dir("proton-shm");
+        let session_info = std::fs::read_to_string("session_info.yaml").unwrap();
+        let vars = [
+            var("SessionNum", VarType::Int, &[0]),
+            var("SessionTick", VarType::Int, &[1234]),
+        ];
+        let (data, _) = build(&vars, &session_info, 0, 1);
+        let memory_map = dir.join("IRSDKMemMapFileName");
+        std::fs::write(&memory_map, data).unwrap();
+
+        let config = ProtonConfig {
+            memory_map,
+            process_name: String::new(),
+        };
+        let mut client = ProtonClient::new(&config);
+        let snapshot = client.get_snapshot().await;
+        assert!(snapshot.connected);
+        assert_eq!(snapshot.tick_count, 1234);
+        assert_eq!(snapshot.session_type(), Some("Offline Testing"));
+
+        // No sim process, no data
+        client.config.process_name = "iRacingSim64DX11.exe".to_string();
+        client.proc_dir = dir.join("proc");
+        assert!(!client.get_snapshot().await.connected);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+use crate::iracing_client::{SimClient, SimSnapshot, Telemetry, TelemetryValue};
+use crate::session_info::{Session, SessionInfo, SessionList};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ScenarioConfig {
+    /// Scenario file, in TOML or YAML format
+    pub file: PathBuf,
+    /// Playback speed, 1.0 is real time
+    pub speed: f64,
+}
+
+/// Fastest playback speed, a whole race weekend in a few seconds
+pub const MAX_SPEED: f64 = 1000.0;
+
+/// Limits a playback speed to what the client can play, with real time in place of nonsense
+pub fn playback_speed(speed: f64) -> f64 {
+    if speed.is_nan() {
+        log::warn!("Invalid scenario speed {speed}, playing in real time");
+        return 1.0;
+    }
+    if !(0.0..=MAX_SPEED).contains(&speed) {
+        log::warn!("Scenario speed {speed} out of range, limiting it to 0 - {MAX_SPEED}");
+    }
+    speed.clamp(0.0, MAX_SPEED)
+}
+
+impl Default for ScenarioConfig {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from("scenarios/race_weekend.toml"),
+            speed: 1.0,
+        }
+    }
+}
+
+/// A timeline of sim states, played back by [`ScenarioClient`]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Scenario {
+    /// Optional session info file used as the base for every step, relative to the scenario file
+    #[serde(default)]
+    pub session_info: Option<PathBuf>,
+    /// Start over from the first step when reaching the end of the timeline
+    #[serde(default, rename = "loop")]
+    pub repeat: bool,
+    pub steps: Vec<ScenarioStep>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ScenarioStep {
+    pub duration_secs: f64,
+    #[serde(default = "default_connected")]
+    pub connected: bool,
+    /// Session type as it appears in the session info, like "Practice" or "Race"
+    #[serde(default)]
+    pub session_type: Option<String>,
+    /// Telemetry values for this step, keyed by irsdk variable name
+    #[serde(default)]
+    pub telemetry: HashMap<String, TelemetryValue>,
+}
+
+fn default_connected() -> bool {
+    true
+}
+
+impl Scenario {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scenario file {path:?}"))?;
+        let scenario: Scenario = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+            _ => toml::from_str(&content)?,
+        };
+        if scenario.steps.is_empty() {
+            anyhow::bail!("Scenario {path:?} has no steps");
+        }
+        Ok(scenario)
+    }
+
+    /// Find the step active at the given (scenario) time, and how far into the step we are
+    fn step_at(&self, time: f64) -> (usize, f64) {
+        let total: f64 = self.steps.iter().map(|step| step.duration_secs).sum();
+        let mut time = if self.repeat && total > 0.0 {
+            time % total
+        } else {
+            time
+        };
+
+        for (index, step) in self.steps.iter().enumerate() {
+            if time < step.duration_secs {
+                return (index, time);
+            }
+            time -= step.duration_secs;
+        }
+
+        // Stay on the last step when the timeline has run out
+        let last = self.steps.len() - 1;
+        (last, time + self.steps[last].duration_secs)
+    }
+
+    /// Every distinct session type in the timeline becomes a session in the session info,
+    /// in order of appearance, like the sessions of a race weekend
+    fn session_types(&self) -> Vec<String> {
+        let mut session_types: Vec<String> = Vec::new();
+        for session_type in self
+            .steps
+            .iter()
+            .filter_map(|step| step.session_type.as_ref())
+        {
+            if !session_types.contains(session_type) {
+                session_types.push(session_type.clone());
+            }
+        }
+        session_types
+    }
+}
+
+/// Plays back a scripted timeline of sim states, in real or accelerated time
+pub struct ScenarioClient {
+    scenario: Scenario,
+    session_info: Arc<SessionInfo>,
+    session_types: Vec<String>,
+    speed: f64,
+    started: Instant,
+}
+
+impl ScenarioClient {
+    pub fn new(config: &ScenarioConfig) -> Result<Self> {
+        let scenario = Scenario::from_file(&config.file)?;
+        let mut session_info = match &scenario.session_info {
+            // Relative to the scenario file
+            Some(path) => {
+                let dir = config.file.parent().unwrap_or(Path::new(""));
+                SessionInfo::from_file(&dir.join(path))?
+            }
+            None => SessionInfo::default(),
+        };
+
+        let session_types = scenario.session_types();
+        if !session_types.is_empty() {
+            session_info.session_info = SessionList {
+                sessions: session_types
+                    .iter()
+                    .enumerate()
+                    .map(|(session_num, session_type)| Session {
+                        session_num: session_num as i32,
+                        session_type: session_type.clone(),
+                        session_name: session_type.to_uppercase(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            };
+        }
+
+        let speed = playback_speed(config.speed);
+        log::info!(
+            "Playing scenario {:?} with {} steps at {}x speed",
+            config.file,
+            scenario.steps.len(),
+            speed
+        );
+        Ok(Self {
+            scenario,
+            session_info: Arc::new(session_info),
+            session_types,
+            speed,
+            started: Instant::now(),
+        })
+    }
+
+    fn scenario_time(&self) -> Duration {
+        let seconds = self.started.elapsed().as_secs_f64() * self.speed;
+        Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX)
+    }
+}
+
+#[async_trait::async_trait]
+impl SimClient for ScenarioClient {
+    async fn get_snapshot(&mut self) -> SimSnapshot {
+        let scenario_time = self.scenario_time();
+        let (index, step_time) = self.scenario.step_at(scenario_time.as_secs_f64());
+        let step = &self.scenario.steps[index];
+        if !step.connected {
+            return SimSnapshot::default();
+        }
+
+        let mut telemetry = step.telemetry.clone();
+        if let Some(session_num) = step
+            .session_type
+            .as_ref()
+            .and_then(|session_type| self.session_types.iter().position(|t| t == session_type))
+        {
+            telemetry
+                .entry("SessionNum".to_string())
+                .or_insert(TelemetryValue::Int(session_num as i64));
+        }
+        telemetry
+            .entry("SessionTime".to_string())
+            .or_insert(TelemetryValue::Float(step_time));
+
+        SimSnapshot {
+            connected: true,
+            session_info: Some(self.session_info.clone()),
+            telemetry: Telemetry::read(&telemetry),
+            tick_count: (scenario_time.as_secs_f64() * 60.0) as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn race_weekend_timeline() {
+        let scenario = Scenario::from_file(Path::new("scenarios/race_weekend.toml")).unwrap();
+        assert_eq!(
+            scenario.session_types(),
+            vec!["Practice", "Qualify", "Race"]
+        );
+
+        let (index, time) = scenario.step_at(0.0);
+        assert_eq!((index, time), (0, 0.0));
+        assert!(!scenario.steps[index].connected);
+
+        let (index, time) = scenario.step_at(15.0);
+        assert_eq!((index, time), (1, 5.0));
+        assert_eq!(
+            scenario.steps[index].session_type.as_deref(),
+            Some("Practice")
+        );
+
+        // The timeline loops
+        let total: f64 = scenario.steps.iter().map(|step| step.duration_secs).sum();
+        assert_eq!(scenario.step_at(total + 15.0), (1, 5.0));
+    }
+
+    #[tokio::test]
+    async fn race_weekend_client() {
+        let config = ScenarioConfig {
+            speed: 0.0,
+            ..Default::default()
+        };
+        let mut client = ScenarioClient::new(&config).unwrap();
+        assert!(!client.get_snapshot().await.connected);
+
+        // Speeds that would overflow the scenario clock are limited
+        assert_eq!(playback_speed(f64::INFINITY), MAX_SPEED);
+        assert_eq!(playback_speed(1e30), MAX_SPEED);
+        assert_eq!(playback_speed(f64::NAN), 1.0);
+        assert_eq!(playback_speed(-2.0), 0.0);
+
+        let session = client.session_info.session(2).unwrap();
+        assert_eq!(session.session_type, "Race");
+        assert_eq!(
+            client.session_info.weekend_info.track_display_name,
+            "Centripetal Circuit"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use super::flags::SessionFlags;
+use crate::irsdk::Sample;
+use crate::session_info::{Session, SessionInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Everything we know about the sim at a single point in time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimSnapshot {
+    pub connected: bool,
+    // Shared, since the session info rarely changes and is passed around with every state update
+    pub session_info: Option<Arc<SessionInfo>>,
+    pub telemetry: Telemetry,
+    pub tick_count: i32,
+}
+
+impl SimSnapshot {
+    /// The currently active session, as given by the `SessionNum` telemetry variable
+    pub fn session(&self) -> Option<&Session> {
+        let session_num = self.telemetry.session_num?;
+        self.session_info.as_ref()?.session(session_num)
+    }
+
+    pub fn session_type(&self) -> Option<&str> {
+        self.session().map(|session| session.session_type.as_str())
+    }
+
+    /// Whether the user joined the session as a spectator, rather than as a driver
+    pub fn is_spectator(&self) -> bool {
+        self.session_info
+            .as_ref()
+            .and_then(|session_info| session_info.player())
+            .is_some_and(|driver| driver.is_spectator != 0)
+    }
+}
+
+/// The subset of live telemetry variables we are interested in
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Telemetry {
+    pub session_num: Option<i32>,
+    pub session_time: Option<f64>,
+    /// Seconds since midnight, local time at the track in the sim
+    pub session_time_of_day: Option<f32>,
+    /// Seconds, a week (604800) in sessions without a time limit
+    pub session_time_remain: Option<f64>,
+    /// Laps left until the checkered flag, 32767 in sessions without a lap limit
+    pub session_laps_remain_ex: Option<i32>,
+    /// irsdk_SessionState, the phase of the current session
+    pub session_state: Option<i32>,
+    pub session_flags: Option<SessionFlags>,
+    pub is_replay_playing: Option<bool>,
+    pub is_on_track: Option<bool>,
+    pub is_in_garage: Option<bool>,
+    pub on_pit_road: Option<bool>,
+    pub player_car_in_pit_stall: Option<bool>,
+    pub lap: Option<i32>,
+    /// Seconds, negative when there is no lap time yet
+    pub lap_last_lap_time: Option<f32>,
+    pub lap_best_lap_time: Option<f32>,
+    pub player_car_position: Option<i32>,
+    pub player_car_class_position: Option<i32>,
+    /// Incidents of the player in the current session
+    pub player_car_my_incident_count: Option<i32>,
+    /// Incidents of the current driver of the player's car, in team sessions
+    pub player_car_driver_incident_count: Option<i32>,
+    /// Incidents of the whole team in the player's car
+    pub player_car_team_incident_count: Option<i32>,
+    /// Liters
+    pub fuel_level: Option<f32>,
+    /// Fraction of the maximum fuel, 0.0 to 1.0
+    pub fuel_level_pct: Option<f32>,
+    /// Celsius
+    pub air_temp: Option<f32>,
+    /// Celsius, the track temperature as measured by the crew
+    pub track_temp_crew: Option<f32>,
+    /// Fraction, 0.0 to 1.0
+    pub precipitation: Option<f32>,
+}
+
+impl Telemetry {
+    pub fn read(source: &impl TelemetrySource) -> Self {
+        Self {
+            session_num: source.read_i32("SessionNum"),
+            session_time: source.read_f64("SessionTime"),
+            session_time_of_day: source.read_f32("SessionTimeOfDay"),
+            session_time_remain: source.read_f64("SessionTimeRemain"),
+            session_laps_remain_ex: source.read_i32("SessionLapsRemainEx"),
+            session_state: source.read_i32("SessionState"),
+            session_flags: source
+                .read_i32("SessionFlags")
+                .map(|bits| SessionFlags::from_bits(bits as u32)),
+            is_replay_playing: source.read_bool("IsReplayPlaying"),
+            is_on_track: source.read_bool("IsOnTrack"),
+            is_in_garage: source.read_bool("IsInGarage"),
+            on_pit_road: source.read_bool("OnPitRoad"),
+            player_car_in_pit_stall: source.read_bool("PlayerCarInPitStall"),
+            lap: source.read_i32("Lap"),
+            lap_last_lap_time: source.read_f32("LapLastLapTime"),
+            lap_best_lap_time: source.read_f32("LapBestLapTime"),
+            player_car_position: source.read_i32("PlayerCarPosition"),
+            player_car_class_position: source.read_i32("PlayerCarClassPosition"),
+            player_car_my_incident_count: source.read_i32("PlayerCarMyIncidentCount"),
+            player_car_driver_incident_count: source.read_i32("PlayerCarDriverIncidentCount"),
+            player_car_team_incident_count: source.read_i32("PlayerCarTeamIncidentCount"),
+            fuel_level: source.read_f32("FuelLevel"),
+            fuel_level_pct: source.read_f32("FuelLevelPct"),
+            air_temp: source.read_f32("AirTemp"),
+            track_temp_crew: source.read_f32("TrackTempCrew"),
+            precipitation: source.read_f32("Precipitation"),
+        }
+    }
+}
+
+/// Typed lookup of telemetry variables by name, implemented by each kind of data source
+pub trait TelemetrySource {
+    fn read_bool(&self, name: &str) -> Option<bool>;
+    fn read_i32(&self, name: &str) -> Option<i32>;
+    fn read_f32(&self, name: &str) -> Option<f32>;
+    fn read_f64(&self, name: &str) -> Option<f64>;
+}
+
+/// A telemetry value given in a config or scenario file
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum TelemetryValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+}
+
+// Fixed telemetry values, keyed by irsdk variable name
+impl TelemetrySource for HashMap<String, TelemetryValue> {
+    fn read_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            TelemetryValue::Bool(value) => Some(*value),
+            TelemetryValue::Int(value) => Some(*value != 0),
+            TelemetryValue::Float(_) => None,
+        }
+    }
+
+    fn read_i32(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            TelemetryValue::Int(value) => i32::try_from(*value).ok(),
+            TelemetryValue::Bool(_) | TelemetryValue::Float(_) => None,
+        }
+    }
+
+    fn read_f32(&self, name: &str) -> Option<f32> {
+        self.read_f64(name).map(|value| value as f32)
+    }
+
+    fn read_f64(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            TelemetryValue::Float(value) => Some(*value),
+            TelemetryValue::Int(value) => Some(*value as f64),
+            TelemetryValue::Bool(_) => None,
+        }
+    }
+}
+
+// A row of irsdk telemetry, from the memory map or an .ibt file
+impl TelemetrySource for Sample<'_> {
+    fn read_bool(&self, name: &str) -> Option<bool> {
+        self.read_name::<bool>(name)
+    }
+
+    fn read_i32(&self, name: &str) -> Option<i32> {
+        self.read_name::<i32>(name)
+    }
+
+    fn read_f32(&self, name: &str) -> Option<f32> {
+        self.read_name::<f32>(name)
+    }
+
+    fn read_f64(&self, name: &str) -> Option<f64> {
+        self.read_name::<f64>(name)
+    }
+}
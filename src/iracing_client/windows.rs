@@ -1,27 +1,39 @@
-use crate::iracing_client::SimClient; // Make sure to import the trait
-use simetry::iracing;
-use std::time::Duration;
-use tokio::time::timeout;
+use super::memory_map::MemoryMapReader;
+use crate::iracing_client::{SimClient, SimSnapshot};
+use crate::irsdk::{to_usize, Header, HEADER_LEN, VAR_HEADER_LEN};
+
+use anyhow::{anyhow, bail, Context, Result};
+use windows::core::w;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::Memory::{
+    MapViewOfFile, OpenFileMappingW, UnmapViewOfFile, VirtualQuery, FILE_MAP_READ,
+    MEMORY_BASIC_INFORMATION, MEMORY_MAPPED_VIEW_ADDRESS,
+};
 
 pub struct IracingClient {
-    client: Option<iracing::Client>,
+    memory: Option<SharedMemory>,
+    reader: MemoryMapReader,
 }
 
 impl IracingClient {
+    pub fn new() -> Self {
+        Self {
+            memory: None,
+            reader: MemoryMapReader::new(),
+        }
+    }
+
     fn is_connected(&self) -> bool {
-        self.client.is_some()
+        self.memory.is_some()
     }
 
-    async fn connect(&mut self) -> bool {
+    fn connect(&mut self) -> bool {
         if !self.is_connected() {
             log::debug!("Waiting for iRacing connection...");
-            let connect_result =
-                timeout(Duration::from_secs(5), iracing::Client::try_connect()).await;
-
-            self.client = match connect_result {
-                Ok(client_result) => client_result.ok(),
-                Err(_elapsed) => {
-                    log::debug!("Connection attempt timed out.");
+            self.memory = match SharedMemory::open() {
+                Ok(memory) => Some(memory),
+                Err(e) => {
+                    log::debug!("iRacing memory map not available: {e:?}");
                     None
                 }
             };
@@ -32,38 +44,161 @@ impl IracingClient {
 
 #[async_trait::async_trait]
 impl SimClient for IracingClient {
-    fn new() -> Self {
-        Self { client: None }
-    }
-
-    async fn get_current_session_type(&mut self) -> Option<String> {
-        if !self.connect().await {
-            return None;
+    async fn get_snapshot(&mut self) -> SimSnapshot {
+        if !self.connect() {
+            return SimSnapshot::default();
         }
 
-        let client = self.client.as_mut().expect("Could not get client as mut");
-        let sim_state = match client.next_sim_state().await {
-            Some(state) => state,
-            None => {
-                // iRacing most likely disconnected, reset client
-                log::info!("Lost connection to iRacing.");
-                self.client = None;
-                return None;
+        let memory = self.memory.as_ref().expect("Could not get memory map");
+        let snapshot = match memory.copy() {
+            Ok(data) => self.reader.snapshot(&data),
+            Err(e) => {
+                log::warn!("Failed to copy the iRacing memory map: {e:?}");
+                SimSnapshot::default()
             }
         };
-        let session_info = sim_state.session_info();
-        let session_num = sim_state.read_name::<i32>("SessionNum")?;
+        if !snapshot.connected {
+            // iRacing is not running (anymore), map the memory again on the next attempt
+            log::debug!("No iRacing data, reconnecting.");
+            self.memory = None;
+        }
+        snapshot
+    }
+}
 
-        let sessions = session_info["SessionInfo"]["Sessions"].as_vec()?;
+// The sim keeps writing to the memory map while we read it, so we never hand out references
+// into it. The parts we decode are copied out, and a copy is only used if the sim didn't move
+// on to another buffer or update the session info meanwhile.
+const MAX_COPY_ATTEMPTS: usize = 4;
 
-        sessions
-            .iter()
-            .find(|session| {
-                session["SessionNum"]
-                    .as_i64()
-                    .is_some_and(|num| num as i32 == session_num)
+/// Read only view of the memory map iRacing shares its telemetry through
+struct SharedMemory {
+    handle: HANDLE,
+    view: MEMORY_MAPPED_VIEW_ADDRESS,
+    len: usize,
+}
+
+// The view is only ever read, and the handles can be used from any thread
+unsafe impl Send for SharedMemory {}
+
+impl SharedMemory {
+    fn open() -> Result<Self> {
+        unsafe {
+            let handle =
+                OpenFileMappingW(FILE_MAP_READ.0, false, w!("Local\\IRSDKMemMapFileName"))?;
+            let view = MapViewOfFile(handle, FILE_MAP_READ, 0, 0, 0);
+            if view.Value.is_null() {
+                let _ = CloseHandle(handle);
+                bail!(
+                    "Failed to map iRacing memory: {:?}",
+                    windows::core::Error::from_win32()
+                );
+            }
+
+            // The mapping doesn't tell its size, but the memory region it lives in does
+            let mut info = MEMORY_BASIC_INFORMATION::default();
+            let len = VirtualQuery(
+                Some(view.Value),
+                &mut info,
+                std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+            );
+            if len == 0 || info.RegionSize < HEADER_LEN {
+                let _ = UnmapViewOfFile(view);
+                let _ = CloseHandle(handle);
+                bail!("Failed to query iRacing memory size");
+            }
+
+            Ok(Self {
+                handle,
+                view,
+                len: info.RegionSize,
             })
-            .and_then(|session| session["SessionType"].as_str())
-            .map(String::from)
+        }
+    }
+
+    /// Copy of the header, the variable headers, the session info and the latest variable
+    /// buffer, at their offsets in the memory map. The rest of the copy is left zeroed.
+    fn copy(&self) -> Result<Vec<u8>> {
+        for _ in 0..MAX_COPY_ATTEMPTS {
+            let mut data = vec![0u8; self.len];
+            self.copy_range(&mut data, 0, HEADER_LEN)?;
+            let header = Header::parse(&data)?;
+            if !header.is_connected() {
+                return Ok(data);
+            }
+
+            let var_headers_len = to_usize(header.num_vars, "number of variables")?
+                .checked_mul(VAR_HEADER_LEN)
+                .context("Too many variables")?;
+            self.copy_range(
+                &mut data,
+                to_usize(header.var_header_offset, "variable header offset")?,
+                var_headers_len,
+            )?;
+            self.copy_range(
+                &mut data,
+                to_usize(header.session_info_offset, "session info offset")?,
+                to_usize(header.session_info_len, "session info length")?,
+            )?;
+
+            let Some((index, buf)) = header
+                .var_bufs
+                .iter()
+                .copied()
+                .enumerate()
+                .max_by_key(|(_, buf)| buf.tick_count)
+            else {
+                bail!("Missing variable buffer");
+            };
+            self.copy_range(
+                &mut data,
+                to_usize(buf.buf_offset, "buffer offset")?,
+                to_usize(header.buf_len, "buffer length")?,
+            )?;
+
+            if self.read_i32(48 + index * 16) == buf.tick_count
+                && self.read_i32(12) == header.session_info_update
+            {
+                return Ok(data);
+            }
+        }
+        bail!("iRacing kept changing the memory map while copying it")
+    }
+
+    /// Copy a range of the view to the same offset in `data`, which is as long as the view
+    fn copy_range(&self, data: &mut [u8], offset: usize, len: usize) -> Result<()> {
+        let target = offset
+            .checked_add(len)
+            .filter(|end| *end <= self.len)
+            .and_then(|end| data.get_mut(offset..end))
+            .ok_or_else(|| anyhow!("Out of bounds copy of {len} bytes at offset {offset}"))?;
+        // The range is inside the view, which is never written through this process
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                (self.view.Value as *const u8).add(offset),
+                target.as_mut_ptr(),
+                len,
+            );
+        }
+        Ok(())
+    }
+
+    /// Read a field of the header straight from the view, to see whether the sim changed it
+    fn read_i32(&self, offset: usize) -> i32 {
+        assert!(offset + 4 <= HEADER_LEN, "Not a header field: {offset}");
+        // The view is at least as long as the header, checked when it was opened
+        let bytes = unsafe {
+            std::ptr::read_volatile((self.view.Value as *const u8).add(offset) as *const [u8; 4])
+        };
+        i32::from_le_bytes(bytes)
+    }
+}
+
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnmapViewOfFile(self.view);
+            let _ = CloseHandle(self.handle);
+        }
     }
 }
@@ -0,0 +1,452 @@
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+
+// Decoding of the binary layout used by the iRacing SDK (irsdk_defines.h), shared by the
+// memory mapped telemetry and the .ibt disk telemetry files. Everything is little endian.
+
+pub mod ibt;
+pub mod memory_map;
+
+pub const HEADER_LEN: usize = 112;
+pub const VAR_HEADER_LEN: usize = 144;
+pub const MAX_BUFS: usize = 4;
+const MAX_STRING: usize = 32;
+const MAX_DESC: usize = 64;
+/// Bit in the header status field, set while the sim is running
+const STATUS_CONNECTED: i32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    pub version: i32,
+    pub status: i32,
+    pub tick_rate: i32,
+    pub session_info_update: i32,
+    pub session_info_len: i32,
+    pub session_info_offset: i32,
+    pub num_vars: i32,
+    pub var_header_offset: i32,
+    pub num_buf: i32,
+    pub buf_len: i32,
+    pub var_bufs: Vec<VarBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VarBuf {
+    pub tick_count: i32,
+    pub buf_offset: i32,
+}
+
+impl Header {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_LEN {
+            bail!("Not enough data for irsdk header ({} bytes)", data.len());
+        }
+
+        let num_buf = read_i32(data, 32)?;
+        if !(0..=MAX_BUFS as i32).contains(&num_buf) {
+            bail!("Invalid number of variable buffers: {num_buf}");
+        }
+        let var_bufs = (0..num_buf as usize)
+            .map(|i| {
+                let offset = 48 + i * 16;
+                Ok(VarBuf {
+                    tick_count: read_i32(data, offset)?,
+                    buf_offset: read_i32(data, offset + 4)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            version: read_i32(data, 0)?,
+            status: read_i32(data, 4)?,
+            tick_rate: read_i32(data, 8)?,
+            session_info_update: read_i32(data, 12)?,
+            session_info_len: read_i32(data, 16)?,
+            session_info_offset: read_i32(data, 20)?,
+            num_vars: read_i32(data, 24)?,
+            var_header_offset: read_i32(data, 28)?,
+            num_buf,
+            buf_len: read_i32(data, 36)?,
+            var_bufs,
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.status & STATUS_CONNECTED != 0
+    }
+
+    /// The session info YAML string. iRacing writes it as ISO-8859-1, padded with NUL bytes.
+    pub fn session_info(&self, data: &[u8]) -> Result<String> {
+        let bytes = slice(
+            data,
+            to_usize(self.session_info_offset, "session info offset")?,
+            to_usize(self.session_info_len, "session info length")?,
+        )?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        Ok(bytes[..end].iter().map(|&b| b as char).collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VarType {
+    Char,
+    Bool,
+    Int,
+    BitField,
+    Float,
+    Double,
+}
+
+impl VarType {
+    fn from_i32(value: i32) -> Result<Self> {
+        let var_type = match value {
+            0 => VarType::Char,
+            1 => VarType::Bool,
+            2 => VarType::Int,
+            3 => VarType::BitField,
+            4 => VarType::Float,
+            5 => VarType::Double,
+            unknown => bail!("Unknown variable type {unknown}"),
+        };
+        Ok(var_type)
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            VarType::Char | VarType::Bool => 1,
+            VarType::Int | VarType::BitField | VarType::Float => 4,
+            VarType::Double => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarHeader {
+    pub var_type: VarType,
+    /// Offset from the start of a buffer row
+    pub offset: usize,
+    /// Number of entries, larger than one for arrays
+    pub count: usize,
+    pub count_as_time: bool,
+    pub name: String,
+    pub desc: String,
+    pub unit: String,
+}
+
+impl VarHeader {
+    fn parse(data: &[u8]) -> Result<Self> {
+        Ok(Self {
+            var_type: VarType::from_i32(read_i32(data, 0)?)?,
+            offset: to_usize(read_i32(data, 4)?, "variable offset")?,
+            count: to_usize(read_i32(data, 8)?, "variable count")?,
+            count_as_time: slice(data, 12, 1)?[0] != 0,
+            name: read_string(data, 16, MAX_STRING)?,
+            desc: read_string(data, 16 + MAX_STRING, MAX_DESC)?,
+            unit: read_string(data, 16 + MAX_STRING + MAX_DESC, MAX_STRING)?,
+        })
+    }
+}
+
+/// The variable headers, for looking up variables by name in a buffer row
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vars {
+    headers: HashMap<String, VarHeader>,
+}
+
+impl Vars {
+    pub fn parse(data: &[u8], header: &Header) -> Result<Self> {
+        let var_header_offset = to_usize(header.var_header_offset, "variable header offset")?;
+        let headers = (0..to_usize(header.num_vars, "number of variables")?)
+            .map(|i| {
+                let offset = i
+                    .checked_mul(VAR_HEADER_LEN)
+                    .and_then(|offset| offset.checked_add(var_header_offset))
+                    .ok_or_else(|| anyhow!("Variable header {i} out of bounds"))?;
+                VarHeader::parse(slice(data, offset, VAR_HEADER_LEN)?)
+            })
+            .map(|var| var.map(|var| (var.name.clone(), var)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(Self { headers })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&VarHeader> {
+        self.headers.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Read the first entry of the named variable from a buffer row
+    pub fn read_name<T: VarData>(&self, row: &[u8], name: &str) -> Option<T> {
+        self.read_index(row, name, 0)
+    }
+
+    /// Read a single entry of the named (array) variable from a buffer row
+    pub fn read_index<T: VarData>(&self, row: &[u8], name: &str, index: usize) -> Option<T> {
+        let var = self.get(name)?;
+        if index >= var.count {
+            return None;
+        }
+        let size = var.var_type.size();
+        let offset = index.checked_mul(size)?.checked_add(var.offset)?;
+        T::read(var.var_type, row.get(offset..offset.checked_add(size)?)?)
+    }
+}
+
+/// A single row of telemetry values
+pub struct Sample<'a> {
+    vars: &'a Vars,
+    row: &'a [u8],
+}
+
+impl Sample<'_> {
+    pub fn read_name<T: VarData>(&self, name: &str) -> Option<T> {
+        self.vars.read_name(self.row, name)
+    }
+}
+
+/// Types that variables can be read as
+pub trait VarData: Sized {
+    fn read(var_type: VarType, bytes: &[u8]) -> Option<Self>;
+}
+
+impl VarData for bool {
+    fn read(var_type: VarType, bytes: &[u8]) -> Option<Self> {
+        match var_type {
+            VarType::Bool | VarType::Char => Some(bytes[0] != 0),
+            _ => None,
+        }
+    }
+}
+
+impl VarData for i32 {
+    fn read(var_type: VarType, bytes: &[u8]) -> Option<Self> {
+        match var_type {
+            VarType::Int | VarType::BitField => Some(i32::from_le_bytes(bytes.try_into().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl VarData for u32 {
+    fn read(var_type: VarType, bytes: &[u8]) -> Option<Self> {
+        match var_type {
+            VarType::Int | VarType::BitField => Some(u32::from_le_bytes(bytes.try_into().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl VarData for f32 {
+    fn read(var_type: VarType, bytes: &[u8]) -> Option<Self> {
+        match var_type {
+            VarType::Float => Some(f32::from_le_bytes(bytes.try_into().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl VarData for f64 {
+    fn read(var_type: VarType, bytes: &[u8]) -> Option<Self> {
+        match var_type {
+            VarType::Double => Some(f64::from_le_bytes(bytes.try_into().ok()?)),
+            VarType::Float => Some(f32::from_le_bytes(bytes.try_into().ok()?) as f64),
+            _ => None,
+        }
+    }
+}
+
+/// An offset or length from the header, which can't be negative in valid data
+pub(crate) fn to_usize(value: i32, what: &str) -> Result<usize> {
+    usize::try_from(value).map_err(|_| anyhow!("Negative {what}: {value}"))
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| anyhow!("Out of bounds read of {len} bytes at offset {offset}"))
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Result<i32> {
+    let bytes = slice(data, offset, 4)?;
+    Ok(i32::from_le_bytes(bytes.try_into()?))
+}
+
+fn read_string(data: &[u8], offset: usize, len: usize) -> Result<String> {
+    let bytes = slice(data, offset, len)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+    Ok(bytes[..end].iter().map(|&b| b as char).collect())
+}
+
+// Helpers for building irsdk data in tests
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+
+    pub struct TestVar {
+        pub name: &'static str,
+        pub var_type: VarType,
+        pub values: Vec<Vec<u8>>,
+    }
+
+    pub fn var<T: ToLeBytes>(name: &'static str, var_type: VarType, values: &[T]) -> TestVar {
+        TestVar {
+            name,
+            var_type,
+            values: values.iter().map(|value| value.to_le_bytes_vec()).collect(),
+        }
+    }
+
+    pub trait ToLeBytes {
+        fn to_le_bytes_vec(&self) -> Vec<u8>;
+    }
+
+    impl ToLeBytes for i32 {
+        fn to_le_bytes_vec(&self) -> Vec<u8> {
+            self.to_le_bytes().to_vec()
+        }
+    }
+
+    impl ToLeBytes for f32 {
+        fn to_le_bytes_vec(&self) -> Vec<u8> {
+            self.to_le_bytes().to_vec()
+        }
+    }
+
+    impl ToLeBytes for f64 {
+        fn to_le_bytes_vec(&self) -> Vec<u8> {
+            self.to_le_bytes().to_vec()
+        }
+    }
+
+    impl ToLeBytes for bool {
+        fn to_le_bytes_vec(&self) -> Vec<u8> {
+            vec![*self as u8]
+        }
+    }
+
+    fn write_i32(data: &mut [u8], offset: usize, value: i32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Build irsdk data with the given variables, where each variable has one value per
+    /// buffer row. `sub_header_len` leaves room for the disk sub header of .ibt files.
+    /// Returns the data and the offset of the first buffer row.
+    pub fn build(
+        vars: &[TestVar],
+        session_info: &str,
+        sub_header_len: usize,
+        num_buf: usize,
+    ) -> (Vec<u8>, usize) {
+        let var_header_offset = HEADER_LEN + sub_header_len;
+        let session_info_offset = var_header_offset + vars.len() * VAR_HEADER_LEN;
+        let session_info_len = session_info.len() + 16;
+        let buf_offset = session_info_offset + session_info_len;
+        let buf_len: usize = vars.iter().map(|var| var.var_type.size()).sum();
+        let num_rows = vars.first().map(|var| var.values.len()).unwrap_or(0);
+
+        let mut data = vec![0u8; buf_offset + buf_len * num_rows.max(num_buf)];
+        write_i32(&mut data, 0, 2);
+        write_i32(&mut data, 4, 1);
+        write_i32(&mut data, 8, 60);
+        write_i32(&mut data, 12, 1);
+        write_i32(&mut data, 16, session_info_len as i32);
+        write_i32(&mut data, 20, session_info_offset as i32);
+        write_i32(&mut data, 24, vars.len() as i32);
+        write_i32(&mut data, 28, var_header_offset as i32);
+        write_i32(&mut data, 32, num_buf as i32);
+        write_i32(&mut data, 36, buf_len as i32);
+        for i in 0..num_buf {
+            write_i32(&mut data, 48 + i * 16, i as i32 + 1);
+            write_i32(
+                &mut data,
+                48 + i * 16 + 4,
+                (buf_offset + i * buf_len) as i32,
+            );
+        }
+
+        let mut var_offset = 0;
+        for (i, var) in vars.iter().enumerate() {
+            let header = var_header_offset + i * VAR_HEADER_LEN;
+            let var_type = var.var_type as i32;
+            write_i32(&mut data, header, var_type);
+            write_i32(&mut data, header + 4, var_offset as i32);
+            write_i32(&mut data, header + 8, 1);
+            data[header + 16..header + 16 + var.name.len()].copy_from_slice(var.name.as_bytes());
+
+            for (row, value) in var.values.iter().enumerate() {
+                let offset = buf_offset + row * buf_len + var_offset;
+                data[offset..offset + value.len()].copy_from_slice(value);
+            }
+            var_offset += var.var_type.size();
+        }
+
+        data[session_info_offset..session_info_offset + session_info.len()]
+            .copy_from_slice(session_info.as_bytes());
+
+        (data, buf_offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{build, var};
+    use super::*;
+
+    #[test]
+    fn decode_header_and_vars() {
+        let vars = [
+            var("SessionNum", VarType::Int, &[2]),
+            var("SessionTime", VarType::Double, &[12.5]),
+            var("IsOnTrack", VarType::Bool, &[true]),
+            var("FuelLevel", VarType::Float, &[42.5f32]),
+        ];
+        let (data, buf_offset) = build(&vars, "---\nWeekendInfo:\n", 0, 1);
+
+        let header = Header::parse(&data).unwrap();
+        assert_eq!(header.tick_rate, 60);
+        assert_eq!(header.num_vars, 4);
+        assert_eq!(header.var_bufs[0].buf_offset as usize, buf_offset);
+        assert_eq!(header.session_info(&data).unwrap(), "---\nWeekendInfo:\n");
+
+        let vars = Vars::parse(&data, &header).unwrap();
+        let row = &data[buf_offset..];
+        assert_eq!(vars.len(), 4);
+        assert_eq!(vars.read_name::<i32>(row, "SessionNum"), Some(2));
+        assert_eq!(vars.read_name::<f64>(row, "SessionTime"), Some(12.5));
+        assert_eq!(vars.read_name::<bool>(row, "IsOnTrack"), Some(true));
+        assert_eq!(vars.read_name::<f32>(row, "FuelLevel"), Some(42.5));
+
+        // Wrong types and unknown names
+        assert_eq!(vars.read_name::<f32>(row, "SessionNum"), None);
+        assert_eq!(vars.read_name::<i32>(row, "Missing"), None);
+        assert_eq!(vars.read_index::<i32>(row, "SessionNum", 1), None);
+    }
+
+    #[test]
+    fn reject_truncated_data() {
+        assert!(Header::parse(&[0u8; 64]).is_err());
+
+        let (data, _) = build(&[var("SessionNum", VarType::Int, &[2])], "", 0, 1);
+        let header = Header::parse(&data).unwrap();
+        assert!(Vars::parse(&data[..HEADER_LEN + 10], &header).is_err());
+    }
+
+    #[test]
+    fn reject_negative_offsets() {
+        let vars = [var("SessionNum", VarType::Int, &[2])];
+        let (mut data, _) = build(&vars, "", 0, 1);
+        let header = Header::parse(&data).unwrap();
+
+        // The offset of the first variable, in its variable header
+        let offset = header.var_header_offset as usize + 4;
+        data[offset..offset + 4].copy_from_slice(&(-8i32).to_le_bytes());
+        assert!(Vars::parse(&data, &header).is_err());
+
+        data[28..32].copy_from_slice(&(-144i32).to_le_bytes());
+        let header = Header::parse(&data).unwrap();
+        assert!(Vars::parse(&data, &header).is_err());
+    }
+}
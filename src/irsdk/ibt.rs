@@ -0,0 +1,152 @@
+use super::{read_i32, slice, to_usize, Header, Sample, Vars, HEADER_LEN};
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+const DISK_SUB_HEADER_LEN: usize = 32;
+
+/// The disk sub header, following the main header in .ibt files
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSubHeader {
+    /// Unix timestamp
+    pub session_start_date: i64,
+    pub session_start_time: f64,
+    pub session_end_time: f64,
+    pub session_lap_count: i32,
+    pub session_record_count: i32,
+}
+
+impl DiskSubHeader {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let bytes = slice(data, HEADER_LEN, DISK_SUB_HEADER_LEN)?;
+        Ok(Self {
+            session_start_date: i64::from_le_bytes(bytes[0..8].try_into()?),
+            session_start_time: f64::from_le_bytes(bytes[8..16].try_into()?),
+            session_end_time: f64::from_le_bytes(bytes[16..24].try_into()?),
+            session_lap_count: read_i32(bytes, 24)?,
+            session_record_count: read_i32(bytes, 28)?,
+        })
+    }
+}
+
+/// An .ibt disk telemetry file, as written by iRacing when telemetry logging is enabled.
+/// The samples are stored back to back, starting at the offset of the first variable buffer.
+pub struct IbtFile {
+    data: Vec<u8>,
+    pub header: Header,
+    pub disk_header: DiskSubHeader,
+    pub vars: Vars,
+    pub session_info: String,
+}
+
+impl IbtFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("Failed to read {path:?}"))?;
+        Self::from_bytes(data).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        let header = Header::parse(&data)?;
+        let disk_header = DiskSubHeader::parse(&data)?;
+        let vars = Vars::parse(&data, &header)?;
+        let session_info = header.session_info(&data)?;
+        if header.var_bufs.is_empty() {
+            anyhow::bail!("Missing variable buffer");
+        }
+        to_usize(header.buf_len, "buffer length")?;
+        to_usize(header.var_bufs[0].buf_offset, "buffer offset")?;
+
+        Ok(Self {
+            data,
+            header,
+            disk_header,
+            vars,
+            session_info,
+        })
+    }
+
+    /// Number of complete samples in the file. Files from sessions that crashed may have a
+    /// record count that doesn't match the data, so we go by the file size.
+    pub fn num_samples(&self) -> usize {
+        let available =
+            self.data.len().saturating_sub(self.samples_offset()) / self.buf_len().max(1);
+        match usize::try_from(self.disk_header.session_record_count) {
+            Ok(count) if count > 0 => count.min(available),
+            _ => available,
+        }
+    }
+
+    pub fn sample(&self, index: usize) -> Option<Sample<'_>> {
+        if index >= self.num_samples() {
+            return None;
+        }
+        let buf_len = self.buf_len();
+        let offset = index
+            .checked_mul(buf_len)?
+            .checked_add(self.samples_offset())?;
+        Some(Sample {
+            vars: &self.vars,
+            row: self.data.get(offset..offset.checked_add(buf_len)?)?,
+        })
+    }
+
+    // Both are checked to not be negative when the file is opened
+    fn samples_offset(&self) -> usize {
+        self.header.var_bufs[0].buf_offset as usize
+    }
+
+    fn buf_len(&self) -> usize {
+        self.header.buf_len as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{build, var};
+    use super::super::VarType;
+    use super::*;
+
+    fn write_sub_header(data: &mut [u8], record_count: i32) {
+        let offset = HEADER_LEN;
+        data[offset..offset + 8].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data[offset + 28..offset + 32].copy_from_slice(&record_count.to_le_bytes());
+    }
+
+    #[test]
+    fn read_samples() {
+        let vars = [
+            var("SessionTick", VarType::Int, &[100, 101, 102]),
+            var("SessionTime", VarType::Double, &[1.0, 1.5, 2.0]),
+        ];
+        let (mut data, _) = build(&vars, "---\nWeekendInfo:\n", DISK_SUB_HEADER_LEN, 1);
+        write_sub_header(&mut data, 3);
+
+        let ibt = IbtFile::from_bytes(data).unwrap();
+        assert_eq!(ibt.disk_header.session_start_date, 1_700_000_000);
+        assert_eq!(ibt.session_info, "---\nWeekendInfo:\n");
+        assert_eq!(ibt.num_samples(), 3);
+
+        let sample = ibt.sample(2).unwrap();
+        assert_eq!(sample.read_name::<i32>("SessionTick"), Some(102));
+        assert_eq!(sample.read_name::<f64>("SessionTime"), Some(2.0));
+        assert!(ibt.sample(3).is_none());
+    }
+
+    #[test]
+    fn record_count_larger_than_data() {
+        let vars = [var("SessionTick", VarType::Int, &[100, 101])];
+        let (mut data, _) = build(&vars, "", DISK_SUB_HEADER_LEN, 1);
+        write_sub_header(&mut data, 1000);
+
+        let ibt = IbtFile::from_bytes(data).unwrap();
+        assert_eq!(ibt.num_samples(), 2);
+    }
+
+    #[test]
+    fn reject_negative_buffer_length() {
+        let vars = [var("SessionTick", VarType::Int, &[100, 101])];
+        let (mut data, _) = build(&vars, "", DISK_SUB_HEADER_LEN, 1);
+        data[36..40].copy_from_slice(&(-4i32).to_le_bytes());
+        assert!(IbtFile::from_bytes(data).is_err());
+    }
+}
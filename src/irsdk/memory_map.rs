@@ -0,0 +1,123 @@
+use super::{slice, to_usize, Header, Sample, Vars};
+
+use anyhow::{bail, Result};
+
+/// Decoder for the live telemetry memory map. Keeps the parsed variable headers between
+/// reads, since they only change when the sim restarts.
+///
+/// The sim keeps rewriting the memory map, so it is decoded from a copy. Making sure the sim
+/// didn't change the parts that are read while they were copied is up to the caller.
+#[derive(Debug, Default)]
+pub struct MemoryMap {
+    vars: Vars,
+    /// Variable count, header offset and row length the variables were parsed for
+    vars_layout: Option<(i32, i32, i32)>,
+    session_info_update: Option<i32>,
+}
+
+/// The latest telemetry row
+pub struct Frame<'a> {
+    /// The session info YAML, only set when it changed since the previous frame
+    pub session_info: Option<String>,
+    vars: &'a Vars,
+    row: &'a [u8],
+}
+
+impl Frame<'_> {
+    pub fn sample(&self) -> Sample<'_> {
+        Sample {
+            vars: self.vars,
+            row: self.row,
+        }
+    }
+}
+
+impl MemoryMap {
+    /// Decode the latest frame from a copy of the memory map. Returns `None` when the sim is
+    /// not running.
+    pub fn read<'a>(&'a mut self, data: &'a [u8]) -> Result<Option<Frame<'a>>> {
+        let header = Header::parse(data)?;
+        if !header.is_connected() {
+            // Start over when the sim comes back
+            self.vars_layout = None;
+            self.session_info_update = None;
+            return Ok(None);
+        }
+
+        let layout = (header.num_vars, header.var_header_offset, header.buf_len);
+        if self.vars_layout != Some(layout) {
+            self.vars = Vars::parse(data, &header)?;
+            self.vars_layout = Some(layout);
+        }
+
+        let session_info = if self.session_info_update != Some(header.session_info_update) {
+            self.session_info_update = Some(header.session_info_update);
+            Some(header.session_info(data)?)
+        } else {
+            None
+        };
+
+        // The sim rotates between the variable buffers, the latest one has the highest tick count
+        let Some(buf) = header.var_bufs.iter().max_by_key(|buf| buf.tick_count) else {
+            bail!("Missing variable buffer");
+        };
+        let row = slice(
+            data,
+            to_usize(buf.buf_offset, "buffer offset")?,
+            to_usize(header.buf_len, "buffer length")?,
+        )?;
+        Ok(Some(Frame {
+            session_info,
+            vars: &self.vars,
+            row,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{build, var};
+    use super::super::VarType;
+    use super::*;
+
+    fn write_i32(data: &mut [u8], offset: usize, value: i32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn read_latest_buffer() {
+        // One row per buffer, with the tick counts 1, 2 and 3
+        let vars = [
+            var("SessionNum", VarType::Int, &[0, 1, 2]),
+            var("SessionTime", VarType::Double, &[10.0, 20.0, 30.0]),
+        ];
+        let (mut data, _) = build(&vars, "---\nWeekendInfo:\n", 0, 3);
+
+        let mut memory_map = MemoryMap::default();
+        let frame = memory_map.read(&data).unwrap().unwrap();
+        assert_eq!(frame.sample().read_name::<i32>("SessionNum"), Some(2));
+        assert_eq!(frame.sample().read_name::<f64>("SessionTime"), Some(30.0));
+        assert_eq!(frame.session_info.as_deref(), Some("---\nWeekendInfo:\n"));
+
+        // The sim rotates to the first buffer, the session info is unchanged
+        write_i32(&mut data, 48, 4);
+        let frame = memory_map.read(&data).unwrap().unwrap();
+        assert_eq!(frame.sample().read_name::<i32>("SessionNum"), Some(0));
+        assert_eq!(frame.session_info, None);
+
+        // A session info update
+        write_i32(&mut data, 12, 2);
+        let frame = memory_map.read(&data).unwrap().unwrap();
+        assert!(frame.session_info.is_some());
+    }
+
+    #[test]
+    fn sim_not_running() {
+        let (mut data, _) = build(&[var("SessionNum", VarType::Int, &[0])], "", 0, 1);
+        write_i32(&mut data, 4, 0);
+
+        let mut memory_map = MemoryMap::default();
+        assert!(memory_map.read(&data).unwrap().is_none());
+        assert!(memory_map.read(&[0u8; 16]).is_err());
+    }
+}
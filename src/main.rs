@@ -3,14 +3,18 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod backend;
+mod cli;
 mod config;
 mod helpers;
 mod iracing_client;
+mod irsdk;
 mod logging;
 mod platform;
 mod resources;
+mod session_info;
 mod sim_monitor;
 mod tray;
+mod units;
 
 #[cfg(feature = "iced_gui")]
 mod frontend;
@@ -89,7 +93,7 @@ impl ApplicationHandler<backend::Event> for Application {
                 sim_monitor::Event::ConnectedToSim(state)
                 | sim_monitor::Event::DisconnectedFromSim(state),
             ) => {
-                self.tray_icon.update_state(state);
+                self.tray_icon.update_state(*state);
             }
             backend::Event::Tray(tray_event) => match tray_event {
                 tray::TrayEventType::MenuItemClicked(menu_item) => match menu_item {
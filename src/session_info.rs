@@ -0,0 +1,446 @@
+// Not every field is consumed by the monitor, but we want the model to mirror the YAML
+#![allow(dead_code)]
+
+use crate::units::{deserialize_quantity, Quantity};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::path::Path;
+use std::str::FromStr;
+
+// Typed model of the session info YAML string that iRacing exposes alongside the telemetry.
+// Only the parts we care about are modelled, everything else is ignored when deserializing.
+// All structs use `#[serde(default)]`, since the available keys vary between sim versions
+// and session types.
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct SessionInfo {
+    pub weekend_info: WeekendInfo,
+    pub session_info: SessionList,
+    pub driver_info: DriverInfo,
+    pub split_time_info: SplitTimeInfo,
+    pub car_setup: CarSetup,
+}
+
+impl SessionInfo {
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        serde_yaml::from_str(yaml).context("Failed to deserialize session info YAML")
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let yaml = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read session info file {path:?}"))?;
+        Self::from_yaml(&yaml)
+            .with_context(|| format!("Failed to parse session info file {path:?}"))
+    }
+
+    /// Get the session with the given `SessionNum`
+    pub fn session(&self, session_num: i32) -> Option<&Session> {
+        self.session_info
+            .sessions
+            .iter()
+            .find(|session| session.session_num == session_num)
+    }
+
+    /// Get the driver entry of the player's car
+    pub fn player(&self) -> Option<&Driver> {
+        self.driver_info.driver(self.driver_info.driver_car_idx)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct WeekendInfo {
+    pub track_name: String,
+    #[serde(rename = "TrackID")]
+    pub track_id: i32,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_length: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_length_official: Option<Quantity>,
+    pub track_display_name: String,
+    pub track_display_short_name: String,
+    pub track_config_name: Option<String>,
+    pub track_city: String,
+    pub track_country: String,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_altitude: Option<Quantity>,
+    /// Degrees
+    #[serde(deserialize_with = "deserialize_degrees")]
+    pub track_latitude: Option<f64>,
+    /// Degrees
+    #[serde(deserialize_with = "deserialize_degrees")]
+    pub track_longitude: Option<f64>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_north_offset: Option<Quantity>,
+    pub track_num_turns: i32,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_pit_speed_limit: Option<Quantity>,
+    pub track_type: String,
+    pub track_direction: String,
+    pub track_weather_type: String,
+    pub track_skies: String,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_surface_temp: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_air_temp: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_air_pressure: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_wind_vel: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_wind_dir: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_relative_humidity: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_fog_level: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub track_precipitation: Option<Quantity>,
+    pub track_cleanup: i32,
+    pub track_dynamic_track: i32,
+    pub track_version: String,
+    #[serde(rename = "SeriesID")]
+    pub series_id: i32,
+    #[serde(rename = "SeasonID")]
+    pub season_id: i32,
+    #[serde(rename = "SessionID")]
+    pub session_id: i32,
+    #[serde(rename = "SubSessionID")]
+    pub sub_session_id: i32,
+    #[serde(rename = "LeagueID")]
+    pub league_id: i32,
+    pub official: i32,
+    pub race_week: i32,
+    pub event_type: String,
+    pub category: String,
+    pub sim_mode: String,
+    pub team_racing: i32,
+    pub min_drivers: i32,
+    pub max_drivers: i32,
+    pub num_car_classes: i32,
+    pub num_car_types: i32,
+    pub heat_racing: i32,
+    pub build_type: String,
+    pub build_target: String,
+    pub build_version: String,
+    pub weekend_options: WeekendOptions,
+    pub telemetry_options: TelemetryOptions,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct WeekendOptions {
+    pub num_starters: i32,
+    pub starting_grid: String,
+    pub qualify_scoring: String,
+    pub course_cautions: String,
+    pub standing_start: i32,
+    pub short_parade_lap: i32,
+    pub restarts: String,
+    pub weather_type: String,
+    pub skies: String,
+    pub wind_direction: String,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub wind_speed: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub weather_temp: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub relative_humidity: Option<Quantity>,
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub fog_level: Option<Quantity>,
+    pub time_of_day: String,
+    pub date: String,
+    pub earth_rotation_speedup_factor: i32,
+    pub unofficial: i32,
+    pub commercial_mode: String,
+    pub night_mode: String,
+    pub is_fixed_setup: i32,
+    pub strict_laps_checking: String,
+    pub has_open_registration: i32,
+    pub hardcore_level: i32,
+    pub num_joker_laps: i32,
+    pub incident_limit: String,
+    pub fast_repairs_limit: String,
+    pub green_white_checkered_limit: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct TelemetryOptions {
+    pub telemetry_disk_file: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct SessionList {
+    pub sessions: Vec<Session>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Session {
+    pub session_num: i32,
+    #[serde(deserialize_with = "deserialize_limit")]
+    pub session_laps: SessionLimit<i32>,
+    #[serde(deserialize_with = "deserialize_limit")]
+    pub session_time: SessionLimit<Quantity>,
+    pub session_num_laps_to_avg: i32,
+    pub session_type: String,
+    pub session_track_rubber_state: String,
+    pub session_name: String,
+    pub session_sub_type: Option<String>,
+    pub session_skipped: i32,
+    pub results_positions: Option<Vec<ResultsPosition>>,
+    pub results_fastest_lap: Vec<ResultsFastestLap>,
+    pub results_average_lap_time: f64,
+    pub results_num_caution_flags: i32,
+    pub results_num_caution_laps: i32,
+    pub results_num_lead_changes: i32,
+    pub results_laps_complete: i32,
+    pub results_official: i32,
+}
+
+/// Lap or time limit of a session, which iRacing gives as a number or "unlimited"
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SessionLimit<T> {
+    #[default]
+    Unlimited,
+    Limited(T),
+}
+
+impl<T: Copy> SessionLimit<T> {
+    pub fn limited(&self) -> Option<T> {
+        match self {
+            SessionLimit::Unlimited => None,
+            SessionLimit::Limited(limit) => Some(*limit),
+        }
+    }
+}
+
+// iRacing reports the track coordinates in degrees, despite the "m" suffix
+fn deserialize_degrees<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserialize_quantity(deserializer)?.map(|quantity| quantity.value))
+}
+
+// Laps come as a YAML number, times as a string like "1800.0000 sec"
+fn deserialize_limit<'de, D, T>(deserializer: D) -> Result<SessionLimit<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Debug,
+{
+    let value = match serde_yaml::Value::deserialize(deserializer)? {
+        serde_yaml::Value::String(value) => value,
+        serde_yaml::Value::Number(value) => value.to_string(),
+        _ => return Ok(SessionLimit::Unlimited),
+    };
+    if value == "unlimited" {
+        return Ok(SessionLimit::Unlimited);
+    }
+    match value.parse::<T>() {
+        Ok(limit) => Ok(SessionLimit::Limited(limit)),
+        Err(e) => {
+            log::warn!("Failed to parse session limit \"{value}\": {e:?}");
+            Ok(SessionLimit::Unlimited)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ResultsPosition {
+    pub position: i32,
+    pub class_position: i32,
+    pub car_idx: i32,
+    pub lap: i32,
+    pub time: f64,
+    pub fastest_lap: i32,
+    pub fastest_time: f64,
+    pub last_time: f64,
+    pub laps_led: i32,
+    pub laps_complete: i32,
+    pub laps_driven: f64,
+    pub incidents: i32,
+    pub reason_out_id: i32,
+    pub reason_out_str: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ResultsFastestLap {
+    pub car_idx: i32,
+    pub fastest_lap: i32,
+    pub fastest_time: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct DriverInfo {
+    pub driver_car_idx: i32,
+    #[serde(rename = "DriverUserID")]
+    pub driver_user_id: i32,
+    pub pace_car_idx: i32,
+    pub driver_car_is_electric: i32,
+    pub driver_car_fuel_kg_per_ltr: f64,
+    pub driver_car_fuel_max_ltr: f64,
+    pub driver_car_max_fuel_pct: f64,
+    pub driver_car_version: String,
+    pub driver_pit_trk_pct: f64,
+    pub driver_car_est_lap_time: f64,
+    pub driver_setup_name: String,
+    pub driver_setup_is_modified: i32,
+    pub driver_setup_load_type_name: String,
+    pub driver_setup_passed_tech: i32,
+    pub driver_incident_count: i32,
+    pub drivers: Vec<Driver>,
+}
+
+impl DriverInfo {
+    /// Get the driver entry for the given car index
+    pub fn driver(&self, car_idx: i32) -> Option<&Driver> {
+        self.drivers.iter().find(|driver| driver.car_idx == car_idx)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Driver {
+    pub car_idx: i32,
+    pub user_name: String,
+    pub abbrev_name: Option<String>,
+    pub initials: Option<String>,
+    #[serde(rename = "UserID")]
+    pub user_id: i32,
+    #[serde(rename = "TeamID")]
+    pub team_id: i32,
+    pub team_name: String,
+    pub car_number: String,
+    pub car_number_raw: i32,
+    pub car_path: String,
+    #[serde(rename = "CarClassID")]
+    pub car_class_id: i32,
+    #[serde(rename = "CarID")]
+    pub car_id: i32,
+    pub car_is_pace_car: i32,
+    #[serde(rename = "CarIsAI")]
+    pub car_is_ai: i32,
+    pub car_is_electric: i32,
+    pub car_screen_name: String,
+    pub car_screen_name_short: String,
+    pub car_class_short_name: Option<String>,
+    pub car_class_est_lap_time: f64,
+    pub i_rating: i32,
+    pub lic_string: String,
+    pub is_spectator: i32,
+    pub cur_driver_incident_count: i32,
+    pub team_incident_count: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct SplitTimeInfo {
+    pub sectors: Vec<Sector>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Sector {
+    pub sector_num: i32,
+    pub sector_start_pct: f64,
+}
+
+// The car setup tree is different for every car, so we only keep the update counter typed
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct CarSetup {
+    pub update_count: i32,
+    #[serde(flatten)]
+    pub values: BTreeMap<String, serde_yaml::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Unit;
+
+    const SESSION_INFO_YAML: &str = include_str!("../session_info.yaml");
+
+    #[test]
+    fn parse_golden_session_info() {
+        let info = SessionInfo::from_yaml(SESSION_INFO_YAML).unwrap();
+
+        let weekend = &info.weekend_info;
+        assert_eq!(weekend.track_display_name, "Centripetal Circuit");
+        assert_eq!(weekend.track_id, 143);
+        assert_eq!(weekend.track_config_name, None);
+        assert_eq!(
+            weekend.track_length,
+            Some(Quantity::new(0.72, Unit::Kilometers))
+        );
+        assert_eq!(weekend.track_longitude, Some(-83.684817));
+        assert_eq!(
+            weekend.track_air_pressure,
+            Some(Quantity::new(28.91, Unit::InchesOfMercury))
+        );
+        assert_eq!(
+            weekend.weekend_options.wind_speed,
+            Some(Quantity::new(3.22, Unit::KilometersPerHour))
+        );
+        assert_eq!(weekend.event_type, "Test");
+        assert_eq!(weekend.weekend_options.time_of_day, "12:00 pm");
+        assert_eq!(weekend.weekend_options.date, "2025-05-15");
+        assert_eq!(weekend.weekend_options.course_cautions, "off");
+
+        let session = info.session(0).unwrap();
+        assert_eq!(session.session_type, "Offline Testing");
+        assert_eq!(session.session_laps, SessionLimit::Unlimited);
+        assert_eq!(session.session_time, SessionLimit::Unlimited);
+        assert_eq!(session.session_sub_type, None);
+        assert_eq!(session.results_positions, None);
+        assert_eq!(session.results_fastest_lap[0].car_idx, 255);
+        assert!(info.session(1).is_none());
+
+        let player = info.player().unwrap();
+        assert_eq!(player.car_screen_name, "BMW M4 GT4");
+        assert_eq!(player.car_number, "64");
+        assert_eq!(player.user_id, 1038408);
+        assert_eq!(info.driver_info.driver_car_fuel_max_ltr, 104.0);
+        assert_eq!(info.driver_info.driver_car_fuel_kg_per_ltr, 0.75);
+
+        assert_eq!(info.split_time_info.sectors.len(), 2);
+        assert_eq!(info.split_time_info.sectors[1].sector_start_pct, 0.5);
+
+        assert_eq!(info.car_setup.update_count, 1);
+        assert!(info.car_setup.values.contains_key("Tires"));
+        assert!(info.car_setup.values.contains_key("Chassis"));
+    }
+
+    #[test]
+    fn parse_partial_session_info() {
+        let yaml = "---\nWeekendInfo:\n  TrackName: spa\n  TrackAirTemp: warm\n";
+        let info = SessionInfo::from_yaml(yaml).unwrap();
+        assert_eq!(info.weekend_info.track_name, "spa");
+        assert_eq!(info.weekend_info.track_air_temp, None);
+        assert_eq!(info.weekend_info.track_length, None);
+        assert!(info.session_info.sessions.is_empty());
+        assert!(info.player().is_none());
+    }
+
+    #[test]
+    fn parse_session_limits() {
+        let yaml = "---\nSessionInfo:\n  Sessions:\n  - SessionNum: 0\n    SessionLaps: 20\n    SessionTime: 1800.0000 sec\n";
+        let info = SessionInfo::from_yaml(yaml).unwrap();
+        let session = info.session(0).unwrap();
+        assert_eq!(session.session_laps.limited(), Some(20));
+        assert_eq!(
+            session.session_time.limited(),
+            Some(Quantity::new(1800.0, Unit::Seconds))
+        );
+    }
+}
@@ -1,6 +1,8 @@
+use crate::cli;
 use crate::config;
 use crate::config::AppConfig;
 use crate::iracing_client;
+use crate::units::{Quantity, Unit, UnitSystem};
 
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -9,45 +11,319 @@ use futures::prelude::sink::SinkExt;
 use futures::prelude::stream::StreamExt;
 use futures::stream::Stream;
 use iced_futures::stream as iced_stream;
-use iracing_client::SimClient;
-use rumqttc::{AsyncClient, MqttOptions, QoS};
-use serde::{Deserialize, Serialize};
+use iracing_client::{Flag, SimClient, SimSnapshot, SimSource};
+use rumqttc::{AsyncClient, LastWill, MqttOptions, QoS};
+use serde::{Deserialize, Serialize, Serializer};
 use std::fmt::{Display, Formatter};
-use std::time::Duration;
-use strum::IntoEnumIterator;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use strum_macros::EnumIter;
 
-#[derive(Debug, Serialize, Clone, PartialEq, EnumIter)]
+// Republished at least this often, well within the expire_after of the entities
+const REPUBLISH_INTERVAL: Duration = Duration::from_secs(10);
+
+mod discovery;
+mod entities;
+mod fuel;
+mod incidents;
+mod time_of_day;
+pub use discovery::DiscoveryConfig;
+use entities::Entity;
+use fuel::FuelTracker;
+use incidents::IncidentTracker;
+
+#[derive(Debug, Clone, PartialEq, EnumIter)]
 pub enum SessionType {
-    // Unknown,
     Disconnected,
+    /// Connected, but the session info doesn't tell the session type (yet)
+    Unknown,
     Practice,
+    OpenQualify,
+    LoneQualify,
     Qualify,
+    Warmup,
     Race,
-    LoneQualify,
+    HeatRace,
+    TimeTrial,
     OfflineTesting,
+    /// A session type we don't know about (yet), with the name iRacing uses for it
+    Other(String),
+}
+
+impl SessionType {
+    fn from_session_type(session_type: &str) -> Self {
+        match session_type {
+            "Practice" => SessionType::Practice,
+            "Open Qualify" => SessionType::OpenQualify,
+            "Lone Qualify" => SessionType::LoneQualify,
+            "Qualify" => SessionType::Qualify,
+            "Warmup" => SessionType::Warmup,
+            "Race" => SessionType::Race,
+            "Heat Race" => SessionType::HeatRace,
+            "Time Trial" => SessionType::TimeTrial,
+            "Offline Testing" => SessionType::OfflineTesting,
+            other => {
+                log::warn!("Unknown session type received: {}", other);
+                SessionType::Other(other.to_string())
+            }
+        }
+    }
+
+    /// The value in the HA enum sensor. Unknown session types share a single option,
+    /// since the options have to be listed up front.
+    fn option(&self) -> String {
+        match self {
+            SessionType::Other(_) => "Other".to_string(),
+            session_type => session_type.to_string(),
+        }
+    }
 }
 
 impl Display for SessionType {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
             SessionType::Disconnected => write!(f, "Disconnected"),
+            SessionType::Unknown => write!(f, "Unknown"),
             SessionType::Practice => write!(f, "Practice"),
+            SessionType::OpenQualify => write!(f, "Open Qualify"),
+            SessionType::LoneQualify => write!(f, "Lone Qualify"),
             SessionType::Qualify => write!(f, "Qualify"),
+            SessionType::Warmup => write!(f, "Warmup"),
             SessionType::Race => write!(f, "Race"),
-            SessionType::LoneQualify => write!(f, "Lone Qualify"),
+            SessionType::HeatRace => write!(f, "Heat Race"),
+            SessionType::TimeTrial => write!(f, "Time Trial"),
             SessionType::OfflineTesting => write!(f, "Offline Testing"),
+            SessionType::Other(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl Serialize for SessionType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.option())
+    }
+}
+
+// Phase of the current session, from the SessionState telemetry variable (irsdk_SessionState)
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
+pub enum SessionPhase {
+    Unknown,
+    GetInCar,
+    Warmup,
+    ParadeLaps,
+    Racing,
+    Checkered,
+    CoolDown,
+}
+
+impl SessionPhase {
+    fn from_session_state(session_state: i32) -> Self {
+        match session_state {
+            1 => SessionPhase::GetInCar,
+            2 => SessionPhase::Warmup,
+            3 => SessionPhase::ParadeLaps,
+            4 => SessionPhase::Racing,
+            5 => SessionPhase::Checkered,
+            6 => SessionPhase::CoolDown,
+            _ => SessionPhase::Unknown,
         }
     }
 }
 
-#[derive(Debug, Serialize, Clone, PartialEq)]
+impl Display for SessionPhase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            SessionPhase::Unknown => write!(f, "Unknown"),
+            SessionPhase::GetInCar => write!(f, "Get in car"),
+            SessionPhase::Warmup => write!(f, "Warmup"),
+            SessionPhase::ParadeLaps => write!(f, "Parade laps"),
+            SessionPhase::Racing => write!(f, "Racing"),
+            SessionPhase::Checkered => write!(f, "Checkered"),
+            SessionPhase::CoolDown => write!(f, "Cool down"),
+        }
+    }
+}
+
+// Serialized as the display name, so the state matches the options of the HA enum sensor
+impl Serialize for SessionPhase {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// Where the player is, derived from the player car telemetry
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
+pub enum DriverStatus {
+    Unknown,
+    Garage,
+    PitRoad,
+    InPitStall,
+    OnTrack,
+    Spectating,
+}
+
+impl DriverStatus {
+    fn from_snapshot(snapshot: &SimSnapshot) -> Self {
+        let telemetry = &snapshot.telemetry;
+        if snapshot.is_spectator() {
+            DriverStatus::Spectating
+        } else if telemetry.is_in_garage == Some(true) {
+            DriverStatus::Garage
+        } else if telemetry.player_car_in_pit_stall == Some(true) {
+            DriverStatus::InPitStall
+        } else if telemetry.on_pit_road == Some(true) {
+            DriverStatus::PitRoad
+        } else if telemetry.is_on_track == Some(true) {
+            DriverStatus::OnTrack
+        } else if telemetry.is_on_track.is_some() {
+            // Connected, but not in a car, so watching someone else drive
+            DriverStatus::Spectating
+        } else {
+            DriverStatus::Unknown
+        }
+    }
+}
+
+impl Display for DriverStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            DriverStatus::Unknown => write!(f, "Unknown"),
+            DriverStatus::Garage => write!(f, "Garage"),
+            DriverStatus::PitRoad => write!(f, "Pit road"),
+            DriverStatus::InPitStall => write!(f, "In pit stall"),
+            DriverStatus::OnTrack => write!(f, "On track"),
+            DriverStatus::Spectating => write!(f, "Spectating"),
+        }
+    }
+}
+
+impl Serialize for DriverStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// Whether the user is driving, or just watching
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
+pub enum ViewingMode {
+    Unknown,
+    Driving,
+    Spectating,
+    Replay,
+}
+
+impl ViewingMode {
+    fn from_snapshot(snapshot: &SimSnapshot) -> Self {
+        if snapshot.telemetry.is_replay_playing == Some(true) {
+            ViewingMode::Replay
+        } else {
+            // Follows the driver status, so the two sensors don't contradict each other
+            match DriverStatus::from_snapshot(snapshot) {
+                DriverStatus::Unknown => ViewingMode::Unknown,
+                DriverStatus::Spectating => ViewingMode::Spectating,
+                _ => ViewingMode::Driving,
+            }
+        }
+    }
+}
+
+impl Display for ViewingMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ViewingMode::Unknown => write!(f, "Unknown"),
+            ViewingMode::Driving => write!(f, "Driving"),
+            ViewingMode::Spectating => write!(f, "Spectating"),
+            ViewingMode::Replay => write!(f, "Replay"),
+        }
+    }
+}
+
+impl Serialize for ViewingMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct SimMonitorState {
     pub connected: bool,
     // in_session: bool,
     pub current_session_type: SessionType,
-    // session_state: String,
+    /// The session type as iRacing names it, with the sub type if there is one
+    pub session_type_name: Option<String>,
+    pub session_phase: SessionPhase,
+    /// The most important flag shown right now
+    pub flag: Option<Flag>,
+    /// Every flag shown right now
+    pub flags: Vec<Flag>,
+    pub driver_status: DriverStatus,
+    pub viewing_mode: ViewingMode,
+    pub track: Option<String>,
+    pub track_config: Option<String>,
+    pub track_country: Option<String>,
+    pub car: Option<String>,
+    pub event_type: Option<String>,
+    pub category: Option<String>,
+    pub official: Option<bool>,
+    pub league_id: Option<i32>,
+    pub lap: Option<i32>,
+    /// Seconds
+    pub last_lap_time: Option<f32>,
+    /// Seconds
+    pub best_lap_time: Option<f32>,
+    pub position: Option<i32>,
+    pub class_position: Option<i32>,
+    pub incidents: Option<i32>,
+    pub driver_incidents: Option<i32>,
+    pub team_incidents: Option<i32>,
+    /// Incident points added since the previous state, sent as an event instead
+    pub new_incidents: Option<i32>,
+    /// Fuel volume and weight are in the configured unit system
+    pub fuel_level: Option<f64>,
+    pub fuel_level_pct: Option<f64>,
+    pub fuel_weight: Option<f64>,
+    pub fuel_per_lap: Option<f64>,
+    pub fuel_laps_remaining: Option<f64>,
+    /// Weather, in the configured unit system. Live telemetry is preferred over the
+    /// session info, since the weather can change during a session.
+    pub air_temp: Option<f64>,
+    pub track_temp: Option<f64>,
+    pub air_pressure: Option<f64>,
+    pub wind_speed: Option<f64>,
+    /// Degrees
+    pub wind_direction: Option<f64>,
+    /// Percentages
+    pub relative_humidity: Option<f64>,
+    pub fog_level: Option<f64>,
+    pub precipitation: Option<f64>,
+    pub skies: Option<String>,
+    pub track_rubber: Option<String>,
+    /// Local time at the track in the sim, like "14:30:00"
+    pub sim_time_of_day: Option<String>,
+    pub sim_date: Option<String>,
+    pub time_speedup: Option<i32>,
+    /// Degrees above the horizon, at the sim time of day
+    pub sun_elevation: Option<f64>,
+    /// Seconds, unset in sessions without a time limit
+    pub session_time_remaining: Option<f64>,
+    /// Unset in sessions without a lap limit
+    pub session_laps_remaining: Option<i32>,
+    /// Estimated end of the session, RFC 3339
+    pub session_ends_at: Option<String>,
     pub timestamp: String,
+    pub snapshot: SimSnapshot,
+}
+
+impl SimMonitorState {
+    /// Whether the states are equal, apart from the time they were taken
+    fn same_as(&self, other: &Self) -> bool {
+        let this = Self {
+            timestamp: other.timestamp.clone(),
+            snapshot: other.snapshot.clone(),
+            ..self.clone()
+        };
+        this == *other
+    }
 }
 
 impl Default for SimMonitorState {
@@ -56,8 +332,53 @@ impl Default for SimMonitorState {
             connected: false,
             // in_session: false,
             current_session_type: SessionType::Disconnected,
-            // session_state: "None".to_string(),
+            session_type_name: None,
+            session_phase: SessionPhase::Unknown,
+            flag: None,
+            flags: Vec::new(),
+            driver_status: DriverStatus::Unknown,
+            viewing_mode: ViewingMode::Unknown,
+            track: None,
+            track_config: None,
+            track_country: None,
+            car: None,
+            event_type: None,
+            category: None,
+            official: None,
+            league_id: None,
+            lap: None,
+            last_lap_time: None,
+            best_lap_time: None,
+            position: None,
+            class_position: None,
+            incidents: None,
+            driver_incidents: None,
+            team_incidents: None,
+            new_incidents: None,
+            fuel_level: None,
+            fuel_level_pct: None,
+            fuel_weight: None,
+            fuel_per_lap: None,
+            fuel_laps_remaining: None,
+            air_temp: None,
+            track_temp: None,
+            air_pressure: None,
+            wind_speed: None,
+            wind_direction: None,
+            relative_humidity: None,
+            fog_level: None,
+            precipitation: None,
+            skies: None,
+            track_rubber: None,
+            sim_time_of_day: None,
+            sim_date: None,
+            time_speedup: None,
+            sun_elevation: None,
+            session_time_remaining: None,
+            session_laps_remaining: None,
+            session_ends_at: None,
             timestamp: Utc::now().to_rfc3339(),
+            snapshot: SimSnapshot::default(),
         }
     }
 }
@@ -82,29 +403,83 @@ impl Default for MqttConfig {
 }
 
 pub struct SimMonitor {
-    iracing: iracing_client::Client,
+    iracing: Box<dyn SimClient + Send>,
+    sim_source: SimSource,
+    unit_system: UnitSystem,
+    /// The Home Assistant entities, with units for the unit system
+    entities: Vec<Entity>,
+    fuel: FuelTracker,
+    incidents: IncidentTracker,
     mqtt: Option<AsyncClient>,
     last_state: Option<SimMonitorState>,
-    mqtt_topic: String,
+    last_publish: Option<Instant>,
+    discovery: DiscoveryConfig,
 
     mqtt_eventloop_handle: Option<tokio::task::JoinHandle<()>>,
     mqtt_eventloop: Option<rumqttc::EventLoop>,
+    /// Set by the event loop when the device has to be registered (again)
+    registration_needed: Arc<tokio::sync::Notify>,
+    /// Discovery configs replaced since the last registration, their entities are removed
+    retired_discovery: Vec<DiscoveryConfig>,
 }
 
 impl SimMonitor {
-    pub fn new(mqtt_config: Option<MqttConfig>) -> Self {
+    pub fn new(
+        mqtt_config: Option<MqttConfig>,
+        sim_source: SimSource,
+        unit_system: UnitSystem,
+        discovery: DiscoveryConfig,
+    ) -> Self {
         let mut monitor = Self {
-            iracing: iracing_client::Client::new(),
+            iracing: iracing_client::create_client(&sim_source),
+            sim_source,
+            unit_system,
+            entities: entities::entities(unit_system),
+            fuel: FuelTracker::default(),
+            incidents: IncidentTracker::default(),
             mqtt: None,
             last_state: None,
-            mqtt_topic: "homeassistant/sensor/iracing/state".to_string(),
+            last_publish: None,
+            discovery,
             mqtt_eventloop_handle: None,
             mqtt_eventloop: None,
+            registration_needed: Arc::new(tokio::sync::Notify::new()),
+            retired_discovery: Vec::new(),
         };
         monitor.set_mqtt_config(mqtt_config);
         monitor
     }
 
+    fn set_sim_source(&mut self, sim_source: SimSource) {
+        if sim_source != self.sim_source {
+            log::info!("Switching sim source to {:?}", sim_source);
+            self.iracing = iracing_client::create_client(&sim_source);
+            self.sim_source = sim_source;
+        }
+    }
+
+    // Takes effect in the discovery config the next time the device is registered
+    fn set_unit_system(&mut self, unit_system: UnitSystem) {
+        if unit_system != self.unit_system {
+            log::info!("Switching unit system to {:?}", unit_system);
+            self.unit_system = unit_system;
+            self.entities = entities::entities(unit_system);
+        }
+    }
+
+    // Takes effect the next time the MQTT connection is set up
+    fn set_discovery_config(&mut self, discovery: DiscoveryConfig) {
+        if discovery != self.discovery {
+            log::info!("Switching discovery config to {:?}", discovery);
+            let previous = std::mem::replace(&mut self.discovery, discovery);
+            if previous.prefix != self.discovery.prefix
+                || previous.node_id != self.discovery.node_id
+            {
+                self.retired_discovery.push(previous);
+            }
+        }
+    }
+
     fn set_mqtt_config(&mut self, mqtt_config: Option<MqttConfig>) {
         // If we have an existing event loop, abort it before creating a new one
         if let Some(handle) = self.mqtt_eventloop_handle.take() {
@@ -118,10 +493,19 @@ impl SimMonitor {
             return;
         };
 
-        let mut mqtt_options =
-            MqttOptions::new("iracing-monitor", mqtt_config.host, mqtt_config.port);
+        let mut mqtt_options = MqttOptions::new(
+            &self.discovery.client_id,
+            mqtt_config.host,
+            mqtt_config.port,
+        );
         mqtt_options.set_keep_alive(Duration::from_secs(5));
         mqtt_options.set_credentials(mqtt_config.user, mqtt_config.password);
+        mqtt_options.set_last_will(LastWill::new(
+            self.discovery.availability_topic(),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
         let (mqtt_client, mqtt_eventloop) = AsyncClient::new(mqtt_options, 10);
 
         // Store the client and event loop
@@ -138,9 +522,56 @@ impl SimMonitor {
         if let Some(mut mqtt_eventloop) = self.mqtt_eventloop.take() {
             // Spawn and store the event loop handle
             log::debug!("Starting MQTT event loop");
+            let mqtt = self.mqtt.clone().expect("MQTT client");
+            let registration_needed = self.registration_needed.clone();
+            let availability_topic = self.discovery.availability_topic();
+            let status_topic = self.discovery.status_topic();
+            let legacy_topic = self.discovery.legacy_config_topic();
             self.mqtt_eventloop_handle = Some(tokio::spawn(async move {
                 loop {
                     match mqtt_eventloop.poll().await {
+                        Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                            // Birth message and subscriptions, on every (re)connect. Can't wait
+                            // for the request queue here, since this task is the one emptying it.
+                            log::debug!("Connected to MQTT broker");
+                            if let Err(e) = mqtt.try_publish(
+                                &availability_topic,
+                                QoS::AtLeastOnce,
+                                true,
+                                "online",
+                            ) {
+                                log::warn!("Failed to publish availability: {e}");
+                            }
+                            if let Err(e) = mqtt.try_subscribe(&status_topic, QoS::AtLeastOnce) {
+                                log::warn!("Failed to subscribe to Home Assistant status: {e}");
+                            }
+                            // The broker sends the retained config of the legacy sensor, if any
+                            if let Err(e) = mqtt.try_subscribe(&legacy_topic, QoS::AtLeastOnce) {
+                                log::warn!(
+                                    "Failed to subscribe to the legacy discovery config: {e}"
+                                );
+                            }
+                            // The broker may have lost the retained discovery configs
+                            registration_needed.notify_one();
+                        }
+                        Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(publish)))
+                            if publish.topic == status_topic =>
+                        {
+                            if publish.payload.as_ref() == b"online" {
+                                log::info!("Home Assistant came online");
+                                registration_needed.notify_one();
+                            }
+                        }
+                        Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(publish)))
+                            if publish.topic == legacy_topic && !publish.payload.is_empty() =>
+                        {
+                            log::info!("Removing the sensor registered by an earlier version");
+                            if let Err(e) =
+                                mqtt.try_publish(&legacy_topic, QoS::AtLeastOnce, true, Vec::new())
+                            {
+                                log::warn!("Failed to remove the legacy sensor: {e}");
+                            }
+                        }
                         Ok(_notification) => {
                             // log::debug!("MQTT event: {:?}", notification);
                         }
@@ -160,29 +591,54 @@ impl SimMonitor {
                 }
             }));
 
+            // The device is registered once the connection is up
             log::debug!("MQTT client set up.");
-
-            // Register the device
-            if let Some(mqtt) = self.mqtt.as_mut() {
-                if let Err(e) = register_device(mqtt).await {
-                    log::warn!("Failed to register MQTT device ({e})");
-                }
-                // Add a small delay to ensure registration is processed
-                tokio::time::sleep(Duration::from_secs(1)).await;
-            }
         } else {
             log::error!("Failed to start MQTT event loop, missing event loop");
         }
     }
 
+    async fn register_device(&mut self) {
+        let Some(mqtt) = self.mqtt.as_mut() else {
+            return;
+        };
+
+        // Remove the entities of earlier node ids and prefixes, so they don't linger in HA
+        let entities = &self.entities;
+        let retired_topics: Vec<String> = self
+            .retired_discovery
+            .iter()
+            .flat_map(|discovery| entities.iter().map(|entity| entity.config_topic(discovery)))
+            .collect();
+        match clear_discovery(mqtt, &retired_topics).await {
+            Ok(()) => self.retired_discovery.clear(),
+            Err(e) => log::warn!("Failed to remove old MQTT entities ({e})"),
+        }
+
+        if let Err(e) = register_device(mqtt, &self.entities, &self.discovery).await {
+            log::warn!("Failed to register MQTT device ({e})");
+        }
+        // Send the current state again on the next update, after the entities exist
+        self.last_state = None;
+    }
+
     async fn publish_state(&mut self, state: &SimMonitorState) -> Result<()> {
-        if Some(state) != self.last_state.as_ref() {
+        // Unchanged states are still sent now and then, so the entities don't expire
+        let changed = self
+            .last_state
+            .as_ref()
+            .is_none_or(|last_state| !last_state.same_as(state));
+        let due = self
+            .last_publish
+            .is_none_or(|last_publish| last_publish.elapsed() >= REPUBLISH_INTERVAL);
+        if changed || due {
             if let Some(mqtt) = self.mqtt.as_mut() {
-                let payload = serde_json::to_string(&state)?;
-                let topic = self.mqtt_topic.clone();
+                let payload =
+                    serde_json::to_string(&entities::state_payload(&self.entities, state))?;
+                let topic = self.discovery.state_topic();
                 log::debug!(
                     "Attempting to publish to topic: {} with payload: {}",
-                    &self.mqtt_topic,
+                    &topic,
                     &payload
                 );
 
@@ -212,6 +668,7 @@ impl SimMonitor {
                     }
                 });
                 self.last_state = Some(state_clone);
+                self.last_publish = Some(Instant::now());
 
                 // Publish attributes
                 // let attributes_json = serde_json::json!({
@@ -244,35 +701,223 @@ impl SimMonitor {
         Ok(())
     }
 
+    async fn publish_incident(&mut self, delta: i32, incidents: Option<i32>) -> Result<()> {
+        let Some(mqtt) = self.mqtt.as_mut() else {
+            return Ok(());
+        };
+        let payload = serde_json::json!({
+            "event_type": incidents::event_type(delta),
+            "delta": delta,
+            "incidents": incidents,
+        });
+        log::info!("Incident: {payload}");
+        let payload = serde_json::to_string(&payload)?;
+        let topic = self.discovery.event_topic("incident");
+
+        // Like the state, in a separate task, so a full request queue doesn't block the monitor
+        let mqtt = mqtt.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(
+                Duration::from_secs(5),
+                mqtt.publish(topic, QoS::AtLeastOnce, false, payload),
+            )
+            .await
+            {
+                Ok(Ok(_)) => log::debug!("Incident event delivered to MQTT event loop"),
+                Ok(Err(e)) => log::warn!("Failed to publish incident event via MQTT: {}", e),
+                Err(_) => log::warn!("MQTT incident publish timed out after 5 seconds"),
+            }
+        });
+        Ok(())
+    }
+
     async fn get_current_state(&mut self) -> SimMonitorState {
-        match self.iracing.get_current_session_type().await {
+        let snapshot = self.iracing.get_snapshot().await;
+        if !snapshot.connected {
+            self.fuel = FuelTracker::default();
+            self.incidents = IncidentTracker::default();
+            return SimMonitorState {
+                snapshot,
+                ..Default::default()
+            };
+        }
+
+        let current_session_type = match snapshot.session_type() {
             Some(session_type) => {
                 log::debug!("Found session_type: {}", session_type);
+                SessionType::from_session_type(session_type)
+            }
+            None => {
+                log::debug!("Connected, but unable to resolve the current session type");
+                SessionType::Unknown
+            }
+        };
 
-                // Convert the string to SessionType
-                let session_type_enum = match session_type.as_str() {
-                    "Practice" => SessionType::Practice,
-                    "Qualify" => SessionType::Qualify,
-                    "Race" => SessionType::Race,
-                    "Lone Qualify" => SessionType::LoneQualify,
-                    "Offline Testing" => SessionType::OfflineTesting,
-                    unknown => {
-                        log::warn!("Unknown session type received: {}", unknown);
-                        SessionType::Disconnected
-                    }
-                };
+        let telemetry = &snapshot.telemetry;
+        let session_phase = telemetry
+            .session_state
+            .map(SessionPhase::from_session_state)
+            .unwrap_or(SessionPhase::Unknown);
+        let session_flags = telemetry.session_flags.unwrap_or_default();
 
-                SimMonitorState {
-                    connected: true,
-                    current_session_type: session_type_enum,
-                    timestamp: Utc::now().to_rfc3339(),
-                }
-            }
-            None => SimMonitorState {
-                connected: false,
-                current_session_type: SessionType::Disconnected,
-                timestamp: Utc::now().to_rfc3339(),
-            },
+        // Fall back to the percentage of a full tank when the fuel level isn't available
+        let driver_info = snapshot.session_info.as_ref().map(|info| &info.driver_info);
+        let fuel_level = telemetry.fuel_level.map(f64::from).or_else(|| {
+            let max_fuel = driver_info?.driver_car_fuel_max_ltr;
+            Some(f64::from(telemetry.fuel_level_pct?) * max_fuel)
+        });
+        self.fuel
+            .update(telemetry.session_num, telemetry.lap, fuel_level);
+        let volume = |liters: f64| {
+            Quantity::new(liters, Unit::Liters)
+                .to_system(self.unit_system)
+                .value
+        };
+        let fuel_weight = fuel_level.zip(driver_info).map(|(liters, driver_info)| {
+            Quantity::new(
+                liters * driver_info.driver_car_fuel_kg_per_ltr,
+                Unit::Kilograms,
+            )
+            .to_system(self.unit_system)
+            .value
+        });
+
+        // The limits from the session info tell us whether the countdowns mean anything
+        let session = snapshot.session();
+        let session_time_remaining = telemetry
+            .session_time_remain
+            .filter(|_| session.is_some_and(|s| s.session_time.limited().is_some()))
+            .map(|remaining| remaining.max(0.0));
+        let session_laps_remaining = telemetry
+            .session_laps_remain_ex
+            .filter(|_| session.is_some_and(|s| s.session_laps.limited().is_some()))
+            .map(|remaining| remaining.max(0));
+        let lap_time =
+            positive(telemetry.lap_last_lap_time).or(positive(telemetry.lap_best_lap_time));
+        let session_ends_at = session_end(session_time_remaining, session_laps_remaining, lap_time);
+
+        let session_type_name = session.map(|session| match &session.session_sub_type {
+            Some(sub_type) => format!("{} ({sub_type})", session.session_type),
+            None => session.session_type.clone(),
+        });
+
+        // Empty strings in the session info mean the value isn't set
+        let weekend_info = snapshot
+            .session_info
+            .as_ref()
+            .map(|info| &info.weekend_info);
+        let text = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+        let player = snapshot
+            .session_info
+            .as_ref()
+            .and_then(|info| info.player());
+
+        let unit_system = self.unit_system;
+        let in_unit = |quantity: Option<Quantity>, unit: Unit| {
+            let unit = display_unit(unit, unit_system);
+            quantity
+                .and_then(|quantity| quantity.convert(unit))
+                .map(|q| q.value)
+        };
+        let live =
+            |value: Option<f32>, unit: Unit| value.map(|v| Quantity::new(f64::from(v), unit));
+        let air_temp = live(telemetry.air_temp, Unit::Celsius)
+            .or(weekend_info.and_then(|info| info.track_air_temp));
+        let track_temp = live(telemetry.track_temp_crew, Unit::Celsius)
+            .or(weekend_info.and_then(|info| info.track_surface_temp));
+        let precipitation = telemetry
+            .precipitation
+            .map(|fraction| Quantity::new(f64::from(fraction) * 100.0, Unit::Percent))
+            .or(weekend_info.and_then(|info| info.track_precipitation));
+
+        let new_incidents = self.incidents.update(
+            telemetry.session_num,
+            telemetry.player_car_my_incident_count,
+        );
+
+        let options = weekend_info.map(|info| &info.weekend_options);
+        let sim_time = options.and_then(|options| {
+            time_of_day::sim_local_time(
+                options,
+                telemetry.session_time_of_day,
+                telemetry.session_time,
+            )
+        });
+        let sun_elevation = sim_time.zip(weekend_info).and_then(|(sim_time, info)| {
+            let latitude = info.track_latitude?;
+            let longitude = info.track_longitude?;
+            Some(time_of_day::sun_elevation(sim_time, latitude, longitude))
+        });
+
+        SimMonitorState {
+            connected: true,
+            current_session_type,
+            session_type_name,
+            session_phase,
+            flag: session_flags.dominant(),
+            flags: session_flags.iter().collect(),
+            driver_status: DriverStatus::from_snapshot(&snapshot),
+            viewing_mode: ViewingMode::from_snapshot(&snapshot),
+            track: weekend_info.and_then(|info| text(&info.track_display_name)),
+            track_config: weekend_info
+                .and_then(|info| info.track_config_name.as_deref())
+                .and_then(text),
+            track_country: weekend_info.and_then(|info| text(&info.track_country)),
+            car: player.and_then(|driver| text(&driver.car_screen_name)),
+            event_type: weekend_info.and_then(|info| text(&info.event_type)),
+            category: weekend_info.and_then(|info| text(&info.category)),
+            official: weekend_info.map(|info| info.official != 0),
+            league_id: weekend_info
+                .map(|info| info.league_id)
+                .filter(|league_id| *league_id != 0),
+            lap: telemetry.lap,
+            last_lap_time: positive(telemetry.lap_last_lap_time),
+            best_lap_time: positive(telemetry.lap_best_lap_time),
+            position: positive(telemetry.player_car_position),
+            class_position: positive(telemetry.player_car_class_position),
+            incidents: telemetry.player_car_my_incident_count,
+            driver_incidents: telemetry.player_car_driver_incident_count,
+            team_incidents: telemetry.player_car_team_incident_count,
+            new_incidents,
+            fuel_level: fuel_level.map(volume),
+            fuel_level_pct: telemetry.fuel_level_pct.map(|pct| f64::from(pct) * 100.0),
+            fuel_weight,
+            fuel_per_lap: self.fuel.per_lap().map(volume),
+            fuel_laps_remaining: fuel_level.and_then(|liters| self.fuel.laps_remaining(liters)),
+            air_temp: in_unit(air_temp, Unit::Celsius),
+            track_temp: in_unit(track_temp, Unit::Celsius),
+            air_pressure: in_unit(
+                weekend_info.and_then(|info| info.track_air_pressure),
+                Unit::Hectopascals,
+            ),
+            wind_speed: in_unit(
+                weekend_info.and_then(|info| info.track_wind_vel),
+                Unit::KilometersPerHour,
+            ),
+            wind_direction: in_unit(
+                weekend_info.and_then(|info| info.track_wind_dir),
+                Unit::Degrees,
+            ),
+            relative_humidity: in_unit(
+                weekend_info.and_then(|info| info.track_relative_humidity),
+                Unit::Percent,
+            ),
+            fog_level: in_unit(
+                weekend_info.and_then(|info| info.track_fog_level),
+                Unit::Percent,
+            ),
+            precipitation: in_unit(precipitation, Unit::Percent),
+            skies: weekend_info.and_then(|info| text(&info.track_skies)),
+            sim_time_of_day: sim_time.map(|time| time.format("%H:%M:%S").to_string()),
+            sim_date: sim_time.map(|time| time.format("%Y-%m-%d").to_string()),
+            time_speedup: options.map(|options| options.earth_rotation_speedup_factor),
+            sun_elevation,
+            track_rubber: session.and_then(|session| text(&session.session_track_rubber_state)),
+            session_time_remaining,
+            session_laps_remaining,
+            session_ends_at,
+            timestamp: Utc::now().to_rfc3339(),
+            snapshot,
         }
     }
     // // Add a cleanup method
@@ -288,6 +933,37 @@ impl SimMonitor {
     // }
 }
 
+/// The unit a quantity is published in, for the given unit system. Metric units are picked
+/// as the reference, like km/h for speeds.
+fn display_unit(unit: Unit, unit_system: UnitSystem) -> Unit {
+    Quantity::new(0.0, unit).to_system(unit_system).unit
+}
+
+// iRacing uses zero or negative values for "no time" and "no position"
+fn positive<T: PartialOrd + Default>(value: Option<T>) -> Option<T> {
+    value.filter(|value| *value > T::default())
+}
+
+// Sessions end at the time limit or the lap limit, whichever comes first. The lap limit
+// is converted to time with the last lap time.
+fn session_end(
+    time_remaining: Option<f64>,
+    laps_remaining: Option<i32>,
+    lap_time: Option<f32>,
+) -> Option<String> {
+    let laps_time = laps_remaining
+        .zip(lap_time)
+        .map(|(laps, lap_time)| f64::from(laps) * f64::from(lap_time));
+    let remaining = match (time_remaining, laps_time) {
+        (Some(time), Some(laps)) => time.min(laps),
+        (time, laps) => time.or(laps)?,
+    };
+
+    // Whole seconds, so the timestamp doesn't change with every update
+    let ends_at = Utc::now() + chrono::Duration::seconds(remaining.round() as i64);
+    Some(ends_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
 impl Drop for SimMonitor {
     fn drop(&mut self) {
         if let Some(handle) = self.mqtt_eventloop_handle.take() {
@@ -299,42 +975,59 @@ impl Drop for SimMonitor {
     }
 }
 
-async fn register_device(mqtt: &mut AsyncClient) -> Result<()> {
+async fn register_device(
+    mqtt: &mut AsyncClient,
+    entities: &[Entity],
+    discovery: &DiscoveryConfig,
+) -> Result<()> {
     // homeassistant/sensor/hp_1231232/config
     // <discovery_prefix>/<component>/[<node_id>/]<object_id>/config
     // Best practice for entities with a unique_id is to set <object_id> to unique_id and omit the <node_id>.
+    for entity in entities {
+        let config = entity.discovery_config(discovery);
+        publish_discovery(mqtt, discovery, &entity.config_topic(discovery), &config).await?;
+    }
+
+    log::info!("Registered device with Home Assistant.");
+    Ok(())
+}
 
-    let configuration_topic = "homeassistant/sensor/iracing/config";
-
-    // Get all session types as strings
-    let options: Vec<String> = SessionType::iter().map(|st| st.to_string()).collect();
-
-    let config = serde_json::json!({
-        "name": "Session type",
-        "state_topic": "homeassistant/sensor/iracing/state",
-        "value_template": "{{ value_json.current_session_type }}",
-        "unique_id": "iracing_session_type",
-        "expire_after": 30,
-        "icon": "mdi:racing-helmet",
-        "device_class": "enum",
-        "options": options,
-        "device": {
-            "identifiers": "my_unique_id",
-            "name": "iRacing Simulator",
-        },
-    });
+// An empty retained config removes the entity from Home Assistant, and the config from the broker
+async fn clear_discovery(mqtt: &mut AsyncClient, topics: &[String]) -> Result<()> {
+    for topic in topics {
+        mqtt.publish(topic, QoS::AtLeastOnce, true, Vec::new())
+            .await
+            .context("Failed to clear MQTT discovery configuration")?;
+    }
+    Ok(())
+}
+
+async fn publish_discovery(
+    mqtt: &mut AsyncClient,
+    discovery: &DiscoveryConfig,
+    topic: &str,
+    config: &serde_json::Value,
+) -> Result<()> {
+    // Leave out unset options instead of sending nulls
+    let mut config = config.clone();
+    if let Some(options) = config.as_object_mut() {
+        options.retain(|_, value| !value.is_null());
+        // Every entity is unavailable while the app isn't connected
+        options.insert(
+            "availability_topic".to_string(),
+            discovery.availability_topic().into(),
+        );
+        options.insert("origin".to_string(), DiscoveryConfig::origin());
+    }
 
     mqtt.publish(
-        configuration_topic,
+        topic,
         QoS::AtLeastOnce,
         true,
         serde_json::to_string(&config)?,
     )
     .await
-    .context("Failed to publish MQTT discovery configuration")?;
-
-    log::info!("Registered device with Home Assistant.");
-    Ok(())
+    .context("Failed to publish MQTT discovery configuration")
 }
 
 // messages to SimMonitor
@@ -356,8 +1049,9 @@ impl Connection {
 #[derive(Debug, Clone)]
 pub enum Event {
     Ready(Connection),
-    ConnectedToSim(SimMonitorState),
-    DisconnectedFromSim(SimMonitorState),
+    // Boxed, the state is much larger than the other variants
+    ConnectedToSim(Box<SimMonitorState>),
+    DisconnectedFromSim(Box<SimMonitorState>),
 }
 
 impl std::fmt::Display for Event {
@@ -371,10 +1065,25 @@ impl std::fmt::Display for Event {
     }
 }
 
+// Sim source given on the command line takes precedence over the config file
+fn get_sim_source(config: Option<&AppConfig>) -> SimSource {
+    cli::args()
+        .sim_source
+        .clone()
+        .or_else(|| config.map(|c| c.sim_source.clone()))
+        .unwrap_or_default()
+}
+
 pub fn connect(config: Option<AppConfig>) -> impl Stream<Item = Event> {
     // Create the monitor
+    let sim_source = get_sim_source(config.as_ref());
+    let unit_system = config.as_ref().map(|c| c.unit_system).unwrap_or_default();
+    let discovery = config
+        .as_ref()
+        .map(|c| c.discovery.clone())
+        .unwrap_or_default();
     let mqtt_config = config.and_then(|c| if c.mqtt_enabled { Some(c.mqtt) } else { None });
-    let mut monitor = SimMonitor::new(mqtt_config);
+    let mut monitor = SimMonitor::new(mqtt_config, sim_source, unit_system, discovery);
 
     iced_stream::channel(100, |mut output| async move {
         // Create channel
@@ -386,6 +1095,7 @@ pub fn connect(config: Option<AppConfig>) -> impl Stream<Item = Event> {
         // Sim state update interval
         const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
         let mut interval = tokio::time::interval(UPDATE_INTERVAL);
+        let registration_needed = monitor.registration_needed.clone();
 
         // Get the initial state
         // let mut previous_state = monitor.get_current_state().await;
@@ -404,6 +1114,9 @@ pub fn connect(config: Option<AppConfig>) -> impl Stream<Item = Event> {
                     match input {
                         Message::UpdateConfig(config) => {
                             log::debug!("Received config update");
+                            monitor.set_sim_source(get_sim_source(Some(&config)));
+                            monitor.set_unit_system(config.unit_system);
+                            monitor.set_discovery_config(config.discovery.clone());
                             if config.mqtt_enabled {
                                 log::info!("Updating mqtt config");
                                 monitor.set_mqtt_config(Some(config.mqtt));
@@ -415,6 +1128,10 @@ pub fn connect(config: Option<AppConfig>) -> impl Stream<Item = Event> {
                         }
                     }
                 }
+                // (Re)connected to the broker, or Home Assistant restarted
+                _ = registration_needed.notified() => {
+                    monitor.register_device().await;
+                }
                 // Periodic state update
                 _ = interval.tick() => {
                     let state = monitor.get_current_state().await;
@@ -422,12 +1139,17 @@ pub fn connect(config: Option<AppConfig>) -> impl Stream<Item = Event> {
                     if let Err(e) = monitor.publish_state(&state).await {
                         log::warn!("Failed to publish state to MQTT: {}", e);
                     }
+                    if let Some(delta) = state.new_incidents {
+                        if let Err(e) = monitor.publish_incident(delta, state.incidents).await {
+                            log::warn!("Failed to publish incident to MQTT: {}", e);
+                        }
+                    }
 
                     // Publish state event
                     let event = if state.connected {
-                        Event::ConnectedToSim(state.clone())
+                        Event::ConnectedToSim(Box::new(state.clone()))
                     } else {
-                        Event::DisconnectedFromSim(state.clone())
+                        Event::DisconnectedFromSim(Box::new(state.clone()))
                     };
                     if let Err(e) = output.send(event).await {
                         log::error!("Failed to send state event: {}", e);
@@ -435,7 +1157,12 @@ pub fn connect(config: Option<AppConfig>) -> impl Stream<Item = Event> {
                     }
 
                     // Check if the state has changed
-                    if state.current_session_type != previous_state.current_session_type || state.connected != previous_state.connected {
+                    if state.current_session_type != previous_state.current_session_type
+                        || state.session_phase != previous_state.session_phase
+                        || state.driver_status != previous_state.driver_status
+                        || state.viewing_mode != previous_state.viewing_mode
+                        || state.connected != previous_state.connected
+                    {
                         log::info!("State changed, new state: {:?}", state);
                     }
                     previous_state = state;
@@ -444,3 +1171,297 @@ pub fn connect(config: Option<AppConfig>) -> impl Stream<Item = Event> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_info::SessionInfo;
+    use iracing_client::{ReplayConfig, Telemetry, TelemetryValue};
+
+    fn snapshot(spectator: bool, telemetry: Telemetry) -> SimSnapshot {
+        let yaml = format!(
+            "---\nDriverInfo:\n  DriverCarIdx: 0\n  Drivers:\n  - CarIdx: 0\n    IsSpectator: {}\n",
+            i32::from(spectator)
+        );
+        SimSnapshot {
+            connected: true,
+            session_info: Some(Arc::new(SessionInfo::from_yaml(&yaml).unwrap())),
+            telemetry,
+            tick_count: 1,
+        }
+    }
+
+    // Replays the golden session info, with fixed telemetry values
+    fn replay_monitor(unit_system: UnitSystem, telemetry: &[(&str, TelemetryValue)]) -> SimMonitor {
+        let mut config = ReplayConfig::default();
+        config.telemetry.extend(
+            telemetry
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone())),
+        );
+        SimMonitor::new(
+            None,
+            SimSource::Replay(config),
+            unit_system,
+            DiscoveryConfig::default(),
+        )
+    }
+
+    fn assert_close(value: Option<f64>, expected: f64) {
+        let value = value.expect("value is set");
+        assert!((value - expected).abs() < 1e-3, "{value} != {expected}");
+    }
+
+    #[test]
+    fn driver_status_and_viewing_mode() {
+        let in_car = |is_on_track, is_in_garage, on_pit_road, in_pit_stall| Telemetry {
+            is_on_track: Some(is_on_track),
+            is_in_garage: Some(is_in_garage),
+            on_pit_road: Some(on_pit_road),
+            player_car_in_pit_stall: Some(in_pit_stall),
+            ..Default::default()
+        };
+        let replay = Telemetry {
+            is_replay_playing: Some(true),
+            ..in_car(false, false, false, false)
+        };
+        let cases = [
+            (
+                false,
+                in_car(true, false, false, false),
+                DriverStatus::OnTrack,
+                ViewingMode::Driving,
+            ),
+            (
+                false,
+                in_car(true, false, true, false),
+                DriverStatus::PitRoad,
+                ViewingMode::Driving,
+            ),
+            (
+                false,
+                in_car(true, false, true, true),
+                DriverStatus::InPitStall,
+                ViewingMode::Driving,
+            ),
+            (
+                false,
+                in_car(false, true, false, false),
+                DriverStatus::Garage,
+                ViewingMode::Driving,
+            ),
+            // Connected, but not in a car
+            (
+                false,
+                in_car(false, false, false, false),
+                DriverStatus::Spectating,
+                ViewingMode::Spectating,
+            ),
+            (
+                true,
+                in_car(true, false, false, false),
+                DriverStatus::Spectating,
+                ViewingMode::Spectating,
+            ),
+            (false, replay, DriverStatus::Spectating, ViewingMode::Replay),
+            // No car telemetry (yet)
+            (
+                false,
+                Telemetry::default(),
+                DriverStatus::Unknown,
+                ViewingMode::Unknown,
+            ),
+        ];
+        for (spectator, telemetry, driver_status, viewing_mode) in cases {
+            let snapshot = snapshot(spectator, telemetry);
+            assert_eq!(
+                DriverStatus::from_snapshot(&snapshot),
+                driver_status,
+                "{:?}",
+                snapshot.telemetry
+            );
+            assert_eq!(
+                ViewingMode::from_snapshot(&snapshot),
+                viewing_mode,
+                "{:?}",
+                snapshot.telemetry
+            );
+        }
+    }
+
+    #[test]
+    fn session_phase_from_session_state() {
+        let cases = [
+            (0, SessionPhase::Unknown),
+            (1, SessionPhase::GetInCar),
+            (2, SessionPhase::Warmup),
+            (3, SessionPhase::ParadeLaps),
+            (4, SessionPhase::Racing),
+            (5, SessionPhase::Checkered),
+            (6, SessionPhase::CoolDown),
+            (7, SessionPhase::Unknown),
+            (-1, SessionPhase::Unknown),
+        ];
+        for (session_state, expected) in cases {
+            assert_eq!(
+                SessionPhase::from_session_state(session_state),
+                expected,
+                "{session_state}"
+            );
+        }
+    }
+
+    #[test]
+    fn unset_times_and_positions() {
+        let cases = [
+            (Some(3), Some(3)),
+            (Some(1), Some(1)),
+            (Some(0), None),
+            (Some(-1), None),
+            (None, None),
+        ];
+        for (position, expected) in cases {
+            assert_eq!(positive(position), expected);
+        }
+
+        let cases = [
+            (Some(92.5), Some(92.5)),
+            (Some(0.0), None),
+            (Some(-1.0), None),
+            (None, None),
+        ];
+        for (lap_time, expected) in cases {
+            assert_eq!(positive::<f32>(lap_time), expected);
+        }
+    }
+
+    #[test]
+    fn session_end_countdown() {
+        // Seconds from now, for the time remaining, the laps remaining and the lap time
+        let cases = [
+            (Some(600.0), None, None, Some(600)),
+            (None, Some(5), Some(90.0), Some(450)),
+            (Some(600.0), Some(5), Some(90.0), Some(450)),
+            (Some(300.0), Some(5), Some(90.0), Some(300)),
+            (None, Some(5), None, None),
+            (Some(0.4), None, None, Some(0)),
+            (None, None, None, None),
+        ];
+        for (time_remaining, laps_remaining, lap_time, expected) in cases {
+            let before = Utc::now();
+            let ends_at = session_end(time_remaining, laps_remaining, lap_time)
+                .map(|ends_at| chrono::DateTime::parse_from_rfc3339(&ends_at).unwrap());
+            match (ends_at, expected) {
+                (Some(ends_at), Some(seconds)) => {
+                    let remaining = (ends_at.with_timezone(&Utc) - before).num_seconds();
+                    // The timestamp is rounded down to whole seconds
+                    assert!(
+                        (seconds - 1..=seconds).contains(&remaining),
+                        "{remaining} for {time_remaining:?} {laps_remaining:?} {lap_time:?}"
+                    );
+                }
+                (ends_at, expected) => assert_eq!(ends_at.is_some(), expected.is_some()),
+            }
+        }
+    }
+
+    #[test]
+    fn session_type_from_session_type() {
+        let cases = [
+            ("Practice", SessionType::Practice),
+            ("Open Qualify", SessionType::OpenQualify),
+            ("Lone Qualify", SessionType::LoneQualify),
+            ("Qualify", SessionType::Qualify),
+            ("Warmup", SessionType::Warmup),
+            ("Race", SessionType::Race),
+            ("Heat Race", SessionType::HeatRace),
+            ("Time Trial", SessionType::TimeTrial),
+            ("Offline Testing", SessionType::OfflineTesting),
+            ("Drag Race", SessionType::Other("Drag Race".to_string())),
+        ];
+        for (name, expected) in cases {
+            let session_type = SessionType::from_session_type(name);
+            assert_eq!(session_type, expected);
+            assert_eq!(session_type.to_string(), name);
+        }
+        assert_eq!(
+            SessionType::Other("Drag Race".to_string()).option(),
+            "Other"
+        );
+    }
+
+    #[tokio::test]
+    async fn weather_in_unit_system() {
+        // Only the session info, 26.11 C air, 40.36 C track, 28.91 Hg, 0.89 m/s wind at 0 rad
+        let state = replay_monitor(UnitSystem::Imperial, &[])
+            .get_current_state()
+            .await;
+        assert_close(state.air_temp, 78.998);
+        assert_close(state.track_temp, 104.648);
+        assert_close(state.air_pressure, 28.91);
+        assert_close(state.wind_speed, 0.89 / 0.44704);
+        assert_close(state.wind_direction, 0.0);
+        assert_close(state.relative_humidity, 45.0);
+        assert_close(state.precipitation, 0.0);
+        assert_eq!(state.skies.as_deref(), Some("Partly Cloudy"));
+
+        // Live telemetry takes precedence over the session info
+        let live = [
+            ("AirTemp", TelemetryValue::Float(20.0)),
+            ("TrackTempCrew", TelemetryValue::Float(30.0)),
+            ("Precipitation", TelemetryValue::Float(0.25)),
+        ];
+        let state = replay_monitor(UnitSystem::Imperial, &live)
+            .get_current_state()
+            .await;
+        assert_close(state.air_temp, 68.0);
+        assert_close(state.track_temp, 86.0);
+        assert_close(state.precipitation, 25.0);
+
+        let state = replay_monitor(UnitSystem::Metric, &live)
+            .get_current_state()
+            .await;
+        assert_close(state.air_temp, 20.0);
+        assert_close(state.track_temp, 30.0);
+        assert_close(state.air_pressure, 28.91 * 33.863886);
+        assert_close(state.wind_speed, 0.89 * 3.6);
+    }
+
+    #[tokio::test]
+    async fn metadata_from_session_info() {
+        let state = replay_monitor(UnitSystem::Metric, &[])
+            .get_current_state()
+            .await;
+        assert_eq!(state.track.as_deref(), Some("Centripetal Circuit"));
+        assert_eq!(state.track_config, None);
+        assert_eq!(state.track_country.as_deref(), Some("USA"));
+        assert_eq!(state.car.as_deref(), Some("BMW M4 GT4"));
+        assert_eq!(state.event_type.as_deref(), Some("Test"));
+        assert_eq!(state.category.as_deref(), Some("Oval"));
+        assert_eq!(state.league_id, None);
+        assert_eq!(state.official, Some(false));
+
+        // Empty strings are unset values
+        let yaml = std::fs::read_to_string("session_info.yaml")
+            .unwrap()
+            .replace("TrackCountry: USA", "TrackCountry: \"\"")
+            .replace("EventType: Test", "EventType: \"\"");
+        let path = std::env::temp_dir().join(format!("session-info-{}.yaml", std::process::id()));
+        std::fs::write(&path, yaml).unwrap();
+        let config = ReplayConfig {
+            files: vec![path.clone()],
+            ..Default::default()
+        };
+        let mut monitor = SimMonitor::new(
+            None,
+            SimSource::Replay(config),
+            UnitSystem::Metric,
+            DiscoveryConfig::default(),
+        );
+        let state = monitor.get_current_state().await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(state.track_country, None);
+        assert_eq!(state.event_type, None);
+        assert_eq!(state.track.as_deref(), Some("Centripetal Circuit"));
+    }
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+
+/// How the monitor shows up in Home Assistant. The defaults are derived from the host name,
+/// so several sim rigs can share a broker without fighting over the same entities.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    /// The topic prefix Home Assistant listens to for MQTT discovery
+    pub prefix: String,
+    /// Unique per installation, used in the topics, the entity ids and the device identifier
+    pub node_id: String,
+    pub device_name: String,
+    pub client_id: String,
+    /// The area Home Assistant puts the device in when it first sees it
+    pub suggested_area: Option<String>,
+    /// Linked from the device page in Home Assistant
+    pub configuration_url: Option<String>,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self::for_host(&hostname())
+    }
+}
+
+impl DiscoveryConfig {
+    fn for_host(hostname: &str) -> Self {
+        let slug = slug(hostname);
+        Self {
+            prefix: "homeassistant".to_string(),
+            node_id: format!("iracing_{slug}"),
+            device_name: format!("iRacing Simulator ({hostname})"),
+            client_id: format!("iracing-monitor-{slug}"),
+            suggested_area: None,
+            configuration_url: repository(),
+        }
+    }
+
+    /// The device block of the discovery configs, describing the rig and the app version
+    pub fn device(&self) -> serde_json::Value {
+        without_nulls(serde_json::json!({
+            "identifiers": [self.node_id],
+            "name": self.device_name,
+            // The app reports on the sim, iRacing didn't make the device
+            "manufacturer": "iRacing Home Assistant Monitor",
+            "model": "iRacing",
+            "sw_version": env!("CARGO_PKG_VERSION"),
+            "hw_version": hostname(),
+            "suggested_area": self.suggested_area,
+            "configuration_url": self.configuration_url,
+        }))
+    }
+
+    /// The app sending the discovery configs
+    pub fn origin() -> serde_json::Value {
+        without_nulls(serde_json::json!({
+            "name": env!("CARGO_PKG_NAME"),
+            "sw_version": env!("CARGO_PKG_VERSION"),
+            "support_url": repository(),
+        }))
+    }
+
+    /// The id of an entity of this installation, also used as the object id in its topic
+    pub fn object_id(&self, name: &str) -> String {
+        format!("{}_{name}", self.node_id)
+    }
+
+    /// The discovery topic of an entity, `<prefix>/<component>/<object_id>/config`
+    pub fn config_topic(&self, component: &str, name: &str) -> String {
+        format!(
+            "{}/{component}/{}/config",
+            self.prefix,
+            self.object_id(name)
+        )
+    }
+
+    /// Where versions before the per-installation ids registered their only sensor
+    pub fn legacy_config_topic(&self) -> String {
+        format!("{}/sensor/iracing/config", self.prefix)
+    }
+
+    /// Where the state of all sensors is published, as JSON
+    pub fn state_topic(&self) -> String {
+        format!("{}/sensor/{}/state", self.prefix, self.node_id)
+    }
+
+    /// "online" while connected, the broker sets it to "offline" when the connection is lost
+    pub fn availability_topic(&self) -> String {
+        format!("{}/sensor/{}/availability", self.prefix, self.node_id)
+    }
+
+    /// Where an event entity fires its events
+    pub fn event_topic(&self, name: &str) -> String {
+        format!("{}/event/{}/{name}", self.prefix, self.node_id)
+    }
+
+    /// Home Assistant announces itself here, discovery has to be sent again when it comes online
+    pub fn status_topic(&self) -> String {
+        format!("{}/status", self.prefix)
+    }
+}
+
+fn hostname() -> String {
+    let hostname = gethostname::gethostname().to_string_lossy().into_owned();
+    if hostname.is_empty() {
+        "localhost".to_string()
+    } else {
+        hostname
+    }
+}
+
+fn repository() -> Option<String> {
+    Some(env!("CARGO_PKG_REPOSITORY").to_string()).filter(|url| !url.is_empty())
+}
+
+// Home Assistant rejects nulls in place of options that aren't set
+fn without_nulls(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(options) = value.as_object_mut() {
+        options.retain(|_, value| !value.is_null());
+    }
+    value
+}
+
+// Topics and entity ids only allow letters, digits, underscores and dashes
+fn slug(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_host_defaults() {
+        let config = DiscoveryConfig::for_host("Sim-Rig.local");
+        assert_eq!(config.node_id, "iracing_sim_rig_local");
+        assert_eq!(config.client_id, "iracing-monitor-sim_rig_local");
+        assert_eq!(config.device_name, "iRacing Simulator (Sim-Rig.local)");
+        assert_eq!(
+            config.config_topic("sensor", "lap"),
+            "homeassistant/sensor/iracing_sim_rig_local_lap/config"
+        );
+        assert_eq!(
+            config.state_topic(),
+            "homeassistant/sensor/iracing_sim_rig_local/state"
+        );
+
+        let device = config.device();
+        assert_eq!(device["identifiers"][0], "iracing_sim_rig_local");
+        assert_eq!(device["sw_version"], env!("CARGO_PKG_VERSION"));
+        assert!(device.get("suggested_area").is_none());
+        assert_eq!(
+            device["configuration_url"],
+            "https://github.com/FSund/ha-iracing-monitor"
+        );
+    }
+}
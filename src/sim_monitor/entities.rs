@@ -0,0 +1,499 @@
+use super::{
+    display_unit, incidents, DiscoveryConfig, DriverStatus, SessionPhase, SessionType,
+    SimMonitorState, ViewingMode,
+};
+use crate::iracing_client::FLAG_PRIORITY;
+use crate::units::{Unit, UnitSystem};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use strum::IntoEnumIterator;
+
+/// Seconds without a state update before Home Assistant shows the entities as unavailable
+const EXPIRE_AFTER: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Component {
+    Sensor,
+    BinarySensor,
+    /// Fired on its own topic, instead of taking a value from the state
+    Event,
+}
+
+impl Component {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Component::Sensor => "sensor",
+            Component::BinarySensor => "binary_sensor",
+            Component::Event => "event",
+        }
+    }
+}
+
+type Extract = Box<dyn Fn(&SimMonitorState) -> Value + Send + Sync>;
+
+/// A Home Assistant entity of the device. The id is used in the entity id, the discovery
+/// topic and as the key of the value in the state payload.
+pub struct Entity {
+    component: Component,
+    id: String,
+    name: String,
+    icon: &'static str,
+    value: Option<Extract>,
+    /// Extra options of the discovery config, like the device class and unit
+    options: Map<String, Value>,
+}
+
+impl Entity {
+    pub fn sensor<T: Serialize>(
+        id: &str,
+        name: &str,
+        icon: &'static str,
+        value: impl Fn(&SimMonitorState) -> T + Send + Sync + 'static,
+    ) -> Self {
+        // Unset values are sent as null, which the template renders as "None", shown as unknown
+        let value = move |state: &SimMonitorState| {
+            serde_json::to_value(value(state)).unwrap_or(Value::Null)
+        };
+        Self::new(Component::Sensor, id, name, icon, Some(Box::new(value)))
+    }
+
+    pub fn binary_sensor(
+        id: &str,
+        name: &str,
+        icon: &'static str,
+        value: impl Fn(&SimMonitorState) -> Option<bool> + Send + Sync + 'static,
+    ) -> Self {
+        let value = move |state: &SimMonitorState| match value(state) {
+            Some(true) => "ON".into(),
+            Some(false) => "OFF".into(),
+            None => Value::Null,
+        };
+        Self::new(
+            Component::BinarySensor,
+            id,
+            name,
+            icon,
+            Some(Box::new(value)),
+        )
+    }
+
+    pub fn event(id: &str, name: &str, icon: &'static str, event_types: &[&str]) -> Self {
+        Self::new(Component::Event, id, name, icon, None).option("event_types", event_types)
+    }
+
+    fn new(
+        component: Component,
+        id: &str,
+        name: &str,
+        icon: &'static str,
+        value: Option<Extract>,
+    ) -> Self {
+        Self {
+            component,
+            id: id.to_string(),
+            name: name.to_string(),
+            icon,
+            value,
+            options: Map::new(),
+        }
+    }
+
+    pub fn option(mut self, key: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or(Value::Null);
+        self.options.insert(key.to_string(), value);
+        self
+    }
+
+    pub fn device_class(self, device_class: &str) -> Self {
+        self.option("device_class", device_class)
+    }
+
+    pub fn unit(self, unit: &str) -> Self {
+        self.option("unit_of_measurement", unit)
+    }
+
+    /// The unit of a quantity, with the device class that goes with it unless one is set.
+    /// Units like % don't tell what is measured, so those need an explicit class.
+    pub fn quantity(mut self, unit: Unit) -> Self {
+        if let Some(device_class) = unit.device_class() {
+            self.options
+                .entry("device_class")
+                .or_insert(device_class.into());
+        }
+        self.unit(unit.unit_of_measurement())
+    }
+
+    pub fn measurement(self) -> Self {
+        self.option("state_class", "measurement")
+    }
+
+    pub fn precision(self, digits: u32) -> Self {
+        self.option("suggested_display_precision", digits)
+    }
+
+    /// An enum sensor with the given states
+    pub fn options(self, options: impl IntoIterator<Item = String>) -> Self {
+        let options: Vec<String> = options.into_iter().collect();
+        self.device_class("enum").option("options", options)
+    }
+
+    pub fn config_topic(&self, discovery: &DiscoveryConfig) -> String {
+        discovery.config_topic(self.component.as_str(), &self.id)
+    }
+
+    pub fn discovery_config(&self, discovery: &DiscoveryConfig) -> Value {
+        let mut config = json!({
+            "name": self.name,
+            "unique_id": discovery.object_id(&self.id),
+            "icon": self.icon,
+            "device": discovery.device(),
+        });
+        let fields = config.as_object_mut().expect("config is an object");
+        match self.component {
+            Component::Event => {
+                fields.insert("state_topic".into(), discovery.event_topic(&self.id).into());
+            }
+            Component::Sensor => {
+                fields.insert("state_topic".into(), discovery.state_topic().into());
+                let template = format!("{{{{ value_json.{} }}}}", self.id);
+                fields.insert("value_template".into(), template.into());
+                fields.insert("expire_after".into(), EXPIRE_AFTER.into());
+            }
+            Component::BinarySensor => {
+                fields.insert("state_topic".into(), discovery.state_topic().into());
+                // Unset values render as "None", which Home Assistant shows as unknown
+                let template = format!("{{{{ value_json.{} }}}}", self.id);
+                fields.insert("value_template".into(), template.into());
+                fields.insert("payload_on".into(), "ON".into());
+                fields.insert("payload_off".into(), "OFF".into());
+                fields.insert("expire_after".into(), EXPIRE_AFTER.into());
+            }
+        }
+        fields.extend(self.options.clone());
+        config
+    }
+}
+
+/// The state payload of all entities, keyed by their ids
+pub fn state_payload(entities: &[Entity], state: &SimMonitorState) -> Value {
+    let mut payload: Map<String, Value> = entities
+        .iter()
+        .filter_map(|entity| Some((entity.id.clone(), entity.value.as_ref()?(state))))
+        .collect();
+    payload.insert("timestamp".into(), state.timestamp.clone().into());
+    Value::Object(payload)
+}
+
+/// Every entity of the device. Units follow the unit system the state is converted to.
+pub fn entities(unit_system: UnitSystem) -> Vec<Entity> {
+    let volume = display_unit(Unit::Liters, unit_system);
+    let weight = display_unit(Unit::Kilograms, unit_system);
+    let temperature = display_unit(Unit::Celsius, unit_system);
+
+    let mut entities = vec![
+        // Session
+        Entity::sensor("session_type", "Session type", "mdi:racing-helmet", |s| {
+            s.current_session_type.option()
+        })
+        .options(SessionType::iter().map(|session_type| session_type.option())),
+        Entity::sensor(
+            "session_type_name",
+            "Session type name",
+            "mdi:racing-helmet",
+            |s| s.session_type_name.clone(),
+        ),
+        Entity::sensor(
+            "session_phase",
+            "Session phase",
+            "mdi:flag-checkered",
+            |s| s.session_phase,
+        )
+        .options(SessionPhase::iter().map(|phase| phase.to_string())),
+        Entity::sensor("driver_status", "Driver status", "mdi:steering", |s| {
+            s.driver_status
+        })
+        .options(DriverStatus::iter().map(|status| status.to_string())),
+        Entity::sensor("viewing_mode", "Viewing mode", "mdi:eye", |s| {
+            s.viewing_mode
+        })
+        .options(ViewingMode::iter().map(|mode| mode.to_string())),
+        // Track, car and event metadata
+        Entity::sensor("track", "Track", "mdi:go-kart-track", |s| s.track.clone()),
+        Entity::sensor(
+            "track_config",
+            "Track configuration",
+            "mdi:go-kart-track",
+            |s| s.track_config.clone(),
+        ),
+        Entity::sensor("track_country", "Track country", "mdi:earth", |s| {
+            s.track_country.clone()
+        }),
+        Entity::sensor("car", "Car", "mdi:car-sports", |s| s.car.clone()),
+        Entity::sensor("event_type", "Event type", "mdi:calendar-star", |s| {
+            s.event_type.clone()
+        }),
+        Entity::sensor("category", "Category", "mdi:shape", |s| s.category.clone()),
+        Entity::sensor("league_id", "League ID", "mdi:account-group", |s| {
+            s.league_id
+        }),
+        Entity::binary_sensor("official", "Official session", "mdi:trophy", |s| s.official),
+        // Lap timing
+        Entity::sensor("lap", "Lap", "mdi:counter", |s| s.lap).measurement(),
+        Entity::sensor("last_lap_time", "Last lap time", "mdi:timer-outline", |s| {
+            s.last_lap_time
+        })
+        .device_class("duration")
+        .unit("s")
+        .measurement(),
+        Entity::sensor(
+            "best_lap_time",
+            "Best lap time",
+            "mdi:timer-star-outline",
+            |s| s.best_lap_time,
+        )
+        .device_class("duration")
+        .unit("s")
+        .measurement(),
+        Entity::sensor("position", "Position", "mdi:podium", |s| s.position).measurement(),
+        Entity::sensor("class_position", "Class position", "mdi:podium", |s| {
+            s.class_position
+        })
+        .measurement(),
+        // Incident points in the current session, they start over in every session
+        Entity::sensor("incidents", "Incidents", "mdi:car-brake-alert", |s| {
+            s.incidents
+        })
+        .unit("x")
+        .option("state_class", "total_increasing"),
+        Entity::sensor(
+            "driver_incidents",
+            "Driver incidents",
+            "mdi:car-brake-alert",
+            |s| s.driver_incidents,
+        )
+        .unit("x")
+        .option("state_class", "total_increasing"),
+        Entity::sensor(
+            "team_incidents",
+            "Team incidents",
+            "mdi:car-brake-alert",
+            |s| s.team_incidents,
+        )
+        .unit("x")
+        .option("state_class", "total_increasing"),
+        // Fired on every new incident, with the points added as the event type
+        Entity::event(
+            "incident",
+            "Incident",
+            "mdi:car-brake-alert",
+            &incidents::EVENT_TYPES,
+        ),
+        // Fuel
+        Entity::sensor("fuel_level", "Fuel level", "mdi:gas-station", |s| {
+            s.fuel_level
+        })
+        .quantity(volume)
+        .measurement()
+        .precision(1),
+        Entity::sensor(
+            "fuel_level_pct",
+            "Fuel level percentage",
+            "mdi:gas-station",
+            |s| s.fuel_level_pct,
+        )
+        .quantity(Unit::Percent)
+        .measurement()
+        .precision(1),
+        Entity::sensor("fuel_weight", "Fuel weight", "mdi:weight", |s| {
+            s.fuel_weight
+        })
+        .quantity(weight)
+        .measurement()
+        .precision(1),
+        Entity::sensor(
+            "fuel_per_lap",
+            "Fuel per lap",
+            "mdi:gas-station-outline",
+            |s| s.fuel_per_lap,
+        )
+        .quantity(volume)
+        .measurement()
+        .precision(1),
+        Entity::sensor(
+            "fuel_laps_remaining",
+            "Fuel laps remaining",
+            "mdi:gas-station-outline",
+            |s| s.fuel_laps_remaining,
+        )
+        .measurement()
+        .precision(1),
+        // Session countdowns
+        Entity::sensor(
+            "session_time_remaining",
+            "Session time remaining",
+            "mdi:timer-sand",
+            |s| s.session_time_remaining,
+        )
+        .device_class("duration")
+        .unit("s"),
+        Entity::sensor(
+            "session_laps_remaining",
+            "Session laps remaining",
+            "mdi:counter",
+            |s| s.session_laps_remaining,
+        ),
+        Entity::sensor(
+            "session_ends_at",
+            "Session ends at",
+            "mdi:flag-checkered",
+            |s| s.session_ends_at.clone(),
+        )
+        .device_class("timestamp"),
+        // Weather
+        Entity::sensor("air_temp", "Air temperature", "mdi:thermometer", |s| {
+            s.air_temp
+        })
+        .quantity(temperature)
+        .measurement()
+        .precision(1),
+        Entity::sensor("track_temp", "Track temperature", "mdi:thermometer", |s| {
+            s.track_temp
+        })
+        .quantity(temperature)
+        .measurement()
+        .precision(1),
+        Entity::sensor("air_pressure", "Air pressure", "mdi:gauge", |s| {
+            s.air_pressure
+        })
+        .quantity(display_unit(Unit::Hectopascals, unit_system))
+        .measurement()
+        .precision(1),
+        Entity::sensor("wind_speed", "Wind speed", "mdi:weather-windy", |s| {
+            s.wind_speed
+        })
+        .device_class("wind_speed")
+        .quantity(display_unit(Unit::KilometersPerHour, unit_system))
+        .measurement()
+        .precision(1),
+        Entity::sensor("wind_direction", "Wind direction", "mdi:compass", |s| {
+            s.wind_direction
+        })
+        .quantity(Unit::Degrees)
+        .measurement()
+        .precision(1),
+        Entity::sensor(
+            "relative_humidity",
+            "Relative humidity",
+            "mdi:water-percent",
+            |s| s.relative_humidity,
+        )
+        .device_class("humidity")
+        .quantity(Unit::Percent)
+        .measurement()
+        .precision(1),
+        Entity::sensor("fog_level", "Fog level", "mdi:weather-fog", |s| s.fog_level)
+            .quantity(Unit::Percent)
+            .measurement()
+            .precision(1),
+        Entity::sensor("precipitation", "Precipitation", "mdi:weather-rainy", |s| {
+            s.precipitation
+        })
+        .quantity(Unit::Percent)
+        .measurement()
+        .precision(1),
+        Entity::sensor("skies", "Skies", "mdi:weather-partly-cloudy", |s| {
+            s.skies.clone()
+        }),
+        Entity::sensor("track_rubber", "Track rubber", "mdi:tire", |s| {
+            s.track_rubber.clone()
+        }),
+        // In-sim time of day
+        Entity::sensor(
+            "sim_time_of_day",
+            "Sim time of day",
+            "mdi:clock-outline",
+            |s| s.sim_time_of_day.clone(),
+        ),
+        Entity::sensor("sim_date", "Sim date", "mdi:calendar", |s| {
+            s.sim_date.clone()
+        })
+        .device_class("date"),
+        Entity::sensor("time_speedup", "Time speedup", "mdi:fast-forward", |s| {
+            s.time_speedup
+        })
+        .unit("x"),
+        Entity::sensor(
+            "sun_elevation",
+            "Sun elevation",
+            "mdi:weather-sunset",
+            |s| s.sun_elevation,
+        )
+        .unit("°"),
+        // The flags that can be dominant, as those are the ones worth automating on
+        Entity::sensor("flag", "Flag", "mdi:flag", |s| match s.flag {
+            Some(flag) => flag.to_string(),
+            None => "None".to_string(),
+        })
+        .options(
+            std::iter::once("None".to_string())
+                .chain(FLAG_PRIORITY.iter().map(|flag| flag.to_string())),
+        ),
+    ];
+
+    for flag in FLAG_PRIORITY {
+        let id = format!("flag_{}", flag.to_string().to_lowercase().replace(' ', "_"));
+        let name = format!("{flag} flag");
+        entities.push(Entity::binary_sensor(&id, &name, "mdi:flag", move |s| {
+            Some(s.flags.contains(&flag))
+        }));
+    }
+
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovery_and_state_from_the_table() {
+        let discovery = DiscoveryConfig {
+            node_id: "iracing_rig".to_string(),
+            ..Default::default()
+        };
+        let entities = entities(UnitSystem::Metric);
+
+        let lap = entities.iter().find(|entity| entity.id == "lap").unwrap();
+        assert_eq!(
+            lap.config_topic(&discovery),
+            "homeassistant/sensor/iracing_rig_lap/config"
+        );
+        let config = lap.discovery_config(&discovery);
+        assert_eq!(config["unique_id"], "iracing_rig_lap");
+        assert_eq!(config["value_template"], "{{ value_json.lap }}");
+        assert_eq!(config["state_class"], "measurement");
+
+        let official = entities
+            .iter()
+            .find(|entity| entity.id == "official")
+            .unwrap()
+            .discovery_config(&discovery);
+        assert_eq!(official["value_template"], "{{ value_json.official }}");
+        assert_eq!(official["payload_off"], "OFF");
+        let payload = state_payload(&entities, &SimMonitorState::default());
+        assert!(payload["official"].is_null());
+
+        let state = SimMonitorState {
+            lap: Some(3),
+            official: Some(true),
+            ..Default::default()
+        };
+        let payload = state_payload(&entities, &state);
+        assert_eq!(payload["lap"], 3);
+        assert_eq!(payload["official"], "ON");
+        assert_eq!(payload["flag"], "None");
+        assert_eq!(payload["track"], Value::Null);
+        assert!(payload.get("incident").is_none());
+    }
+}
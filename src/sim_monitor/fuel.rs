@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+// Number of laps the consumption estimate is averaged over
+const WINDOW: usize = 5;
+
+/// Rolling estimate of the fuel used per lap, from the fuel level at each lap crossing.
+/// Laps during which the car was refueled, or that were skipped (tows, resets), are left out.
+#[derive(Debug, Default)]
+pub struct FuelTracker {
+    session_num: Option<i32>,
+    lap: Option<i32>,
+    lap_start_fuel: Option<f64>,
+    /// Fuel level at the previous update, to notice refueling
+    last_fuel: Option<f64>,
+    laps: VecDeque<f64>,
+}
+
+impl FuelTracker {
+    pub fn update(&mut self, session_num: Option<i32>, lap: Option<i32>, fuel_level: Option<f64>) {
+        if session_num != self.session_num {
+            *self = Self {
+                session_num,
+                ..Default::default()
+            };
+        }
+        let (Some(lap), Some(fuel_level)) = (lap, fuel_level) else {
+            return;
+        };
+        // Any increase is fuel added, even when the level ends up below the start of the lap
+        let refueled = self.last_fuel.is_some_and(|last| fuel_level > last);
+        self.last_fuel = Some(fuel_level);
+
+        match self.lap {
+            Some(previous) if lap == previous => {
+                if refueled {
+                    // This lap doesn't tell us anything
+                    self.lap_start_fuel = None;
+                }
+            }
+            Some(previous) if lap == previous + 1 => {
+                if let Some(start) = self.lap_start_fuel.filter(|_| !refueled) {
+                    let used = start - fuel_level;
+                    if used > 0.0 {
+                        self.laps.push_back(used);
+                        if self.laps.len() > WINDOW {
+                            self.laps.pop_front();
+                        }
+                    }
+                }
+                self.lap = Some(lap);
+                self.lap_start_fuel = Some(fuel_level);
+            }
+            _ => {
+                // Joined mid lap, or skipped laps. Measuring starts at the next lap crossing.
+                self.lap = Some(lap);
+                self.lap_start_fuel = None;
+            }
+        }
+    }
+
+    /// Average fuel used per lap, in the unit of the fuel level
+    pub fn per_lap(&self) -> Option<f64> {
+        if self.laps.is_empty() {
+            return None;
+        }
+        Some(self.laps.iter().sum::<f64>() / self.laps.len() as f64)
+    }
+
+    pub fn laps_remaining(&self, fuel_level: f64) -> Option<f64> {
+        self.per_lap().map(|per_lap| fuel_level / per_lap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_consumption() {
+        let mut tracker = FuelTracker::default();
+        // Connected late in a lap, the partial lap isn't measured
+        tracker.update(Some(0), Some(0), Some(50.4));
+        tracker.update(Some(0), Some(1), Some(50.0));
+        assert_eq!(tracker.per_lap(), None);
+        tracker.update(Some(0), Some(1), Some(48.0));
+        assert_eq!(tracker.per_lap(), None);
+
+        tracker.update(Some(0), Some(2), Some(47.0));
+        tracker.update(Some(0), Some(3), Some(43.0));
+        assert_eq!(tracker.per_lap(), Some(3.5));
+        assert_eq!(tracker.laps_remaining(35.0), Some(10.0));
+
+        // A lap with a pit stop is skipped
+        tracker.update(Some(0), Some(3), Some(80.0));
+        tracker.update(Some(0), Some(4), Some(78.0));
+        assert_eq!(tracker.per_lap(), Some(3.5));
+        tracker.update(Some(0), Some(5), Some(75.0));
+        assert_eq!(tracker.per_lap(), Some(10.0 / 3.0));
+
+        // A short stop that ends below the fuel level at the start of the lap
+        tracker.update(Some(0), Some(6), Some(71.0));
+        assert_eq!(tracker.per_lap(), Some(3.5));
+        tracker.update(Some(0), Some(6), Some(53.0));
+        tracker.update(Some(0), Some(6), Some(63.0));
+        tracker.update(Some(0), Some(7), Some(61.0));
+        assert_eq!(tracker.per_lap(), Some(3.5));
+        tracker.update(Some(0), Some(8), Some(57.0));
+        assert_eq!(tracker.per_lap(), Some(3.6));
+
+        // A reset skips ahead mid lap, the lap after it is not measured either
+        tracker.update(Some(0), Some(10), Some(56.5));
+        tracker.update(Some(0), Some(11), Some(56.0));
+        assert_eq!(tracker.per_lap(), Some(3.6));
+
+        // Starts over in the next session
+        tracker.update(Some(1), Some(1), Some(100.0));
+        assert_eq!(tracker.per_lap(), None);
+    }
+}
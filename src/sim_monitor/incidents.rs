@@ -0,0 +1,54 @@
+/// Follows the incident count of the player through a session, to tell when new incidents
+/// were given. The count starts over in every session, which isn't an increase.
+#[derive(Debug, Default)]
+pub struct IncidentTracker {
+    session_num: Option<i32>,
+    count: Option<i32>,
+}
+
+impl IncidentTracker {
+    /// Returns the number of incident points added since the previous update, if any
+    pub fn update(&mut self, session_num: Option<i32>, count: Option<i32>) -> Option<i32> {
+        if session_num != self.session_num {
+            *self = Self { session_num, count };
+            return None;
+        }
+        let count = count?;
+        let previous = self.count.replace(count);
+        previous
+            .map(|previous| count - previous)
+            .filter(|delta| *delta > 0)
+    }
+}
+
+/// The name of the HA event type for an increase of the incident count, like "4x"
+pub fn event_type(delta: i32) -> String {
+    match delta {
+        1 | 2 | 4 => format!("{delta}x"),
+        _ => "other".to_string(),
+    }
+}
+
+/// All event types [`event_type`] can return, for the discovery config
+pub const EVENT_TYPES: [&str; 4] = ["1x", "2x", "4x", "other"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incident_increases() {
+        let mut tracker = IncidentTracker::default();
+        assert_eq!(tracker.update(Some(0), Some(3)), None);
+        assert_eq!(tracker.update(Some(0), Some(3)), None);
+        assert_eq!(tracker.update(Some(0), Some(5)), Some(2));
+        assert_eq!(tracker.update(Some(0), None), None);
+        assert_eq!(tracker.update(Some(0), Some(9)), Some(4));
+
+        // A new session starts from zero without an event
+        assert_eq!(tracker.update(Some(1), Some(0)), None);
+        assert_eq!(tracker.update(Some(1), Some(1)), Some(1));
+        assert_eq!(event_type(1), "1x");
+        assert_eq!(event_type(3), "other");
+    }
+}
@@ -0,0 +1,111 @@
+use crate::session_info::WeekendOptions;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+
+/// The local date and time at the track in the sim. `SessionTimeOfDay` is seconds since
+/// midnight on the date of the event. Without it, we start from the time of day in the
+/// weekend options and let the clock run at the sim's speedup factor.
+pub fn sim_local_time(
+    options: &WeekendOptions,
+    session_time_of_day: Option<f32>,
+    session_time: Option<f64>,
+) -> Option<NaiveDateTime> {
+    let date = NaiveDate::parse_from_str(&options.date, "%Y-%m-%d").ok()?;
+    let seconds = match session_time_of_day {
+        Some(seconds) => f64::from(seconds),
+        None => {
+            // Like "12:00 pm"
+            let start =
+                NaiveTime::parse_from_str(&options.time_of_day.to_uppercase(), "%I:%M %p").ok()?;
+            let speedup = f64::from(options.earth_rotation_speedup_factor.max(1));
+            f64::from(start.num_seconds_from_midnight()) + session_time? * speedup
+        }
+    };
+    date.and_hms_opt(0, 0, 0)?
+        .checked_add_signed(TimeDelta::milliseconds((seconds * 1000.0) as i64))
+}
+
+/// Approximate elevation of the sun above the horizon in degrees, using the NOAA
+/// solar position equations. The sim gives us the local time at the track, but not the
+/// time zone, so the time zone is estimated from the longitude and DST is ignored.
+/// That can put the sun an hour or two off, which is still fine for setting the mood.
+pub fn sun_elevation(local_time: NaiveDateTime, latitude: f64, longitude: f64) -> f64 {
+    let hour = f64::from(local_time.num_seconds_from_midnight()) / 3600.0;
+    let day_of_year = f64::from(local_time.ordinal());
+    let gamma = 2.0 * std::f64::consts::PI / 365.0 * (day_of_year - 1.0 + (hour - 12.0) / 24.0);
+
+    // Equation of time in minutes, and the declination of the sun in radians
+    let equation_of_time = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2.0 * gamma).cos()
+            - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos()
+        + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+
+    let time_zone = (longitude / 15.0).round();
+    let time_offset = equation_of_time + 4.0 * longitude - 60.0 * time_zone;
+    let true_solar_time = hour * 60.0 + time_offset;
+    let hour_angle = (true_solar_time / 4.0 - 180.0).to_radians();
+
+    let latitude = latitude.to_radians();
+    let cos_zenith =
+        latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos();
+    90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: (i32, u32, u32), time: (u32, u32)) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+            .unwrap()
+            .and_hms_opt(time.0, time.1, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn sun_over_the_track() {
+        // Around noon in Michigan in May, the sun is high
+        let noon = sun_elevation(at((2025, 5, 15), (12, 0)), 42.6, -83.7);
+        assert!((60.0..70.0).contains(&noon), "{noon}");
+
+        // and it's below the horizon at midnight
+        let midnight = sun_elevation(at((2025, 5, 15), (0, 0)), 42.6, -83.7);
+        assert!(midnight < -10.0, "{midnight}");
+
+        // Midsummer at Spa, the sun has set by 23:00
+        let night = sun_elevation(at((2025, 6, 21), (23, 0)), 50.4, 5.97);
+        assert!(night < 0.0, "{night}");
+        let afternoon = sun_elevation(at((2025, 6, 21), (13, 0)), 50.4, 5.97);
+        assert!(afternoon > 45.0, "{afternoon}");
+    }
+
+    #[test]
+    fn sim_clock() {
+        let options = WeekendOptions {
+            time_of_day: "12:00 pm".to_string(),
+            date: "2025-05-15".to_string(),
+            earth_rotation_speedup_factor: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            sim_local_time(&options, Some(52200.0), Some(10.0)),
+            Some(at((2025, 5, 15), (14, 30)))
+        );
+        // An hour into the session at 4x speed
+        assert_eq!(
+            sim_local_time(&options, None, Some(3600.0)),
+            Some(at((2025, 5, 15), (16, 0)))
+        );
+        // Past midnight
+        assert_eq!(
+            sim_local_time(&options, Some(90000.0), None),
+            Some(at((2025, 5, 16), (1, 0)))
+        );
+    }
+}
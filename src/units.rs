@@ -0,0 +1,341 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// The session info YAML encodes physical quantities as strings with a unit suffix,
+// like "0.72 km", "26.11 C" or "28.91 Hg". This module parses those into typed
+// quantities, and converts them to the unit system selected in the app config.
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Kilometers,
+    Miles,
+    Meters,
+    Feet,
+    Millimeters,
+    Inches,
+    Celsius,
+    Fahrenheit,
+    InchesOfMercury,
+    Hectopascals,
+    Kilopascals,
+    Psi,
+    MetersPerSecond,
+    KilometersPerHour,
+    MilesPerHour,
+    Radians,
+    Degrees,
+    Percent,
+    Liters,
+    Gallons,
+    Kilograms,
+    Pounds,
+    Seconds,
+}
+
+impl Unit {
+    /// Unit string as expected by Home Assistant's `unit_of_measurement`
+    pub fn unit_of_measurement(&self) -> &'static str {
+        match self {
+            Unit::Kilometers => "km",
+            Unit::Miles => "mi",
+            Unit::Meters => "m",
+            Unit::Feet => "ft",
+            Unit::Millimeters => "mm",
+            Unit::Inches => "in",
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::InchesOfMercury => "inHg",
+            Unit::Hectopascals => "hPa",
+            Unit::Kilopascals => "kPa",
+            Unit::Psi => "psi",
+            Unit::MetersPerSecond => "m/s",
+            Unit::KilometersPerHour => "km/h",
+            Unit::MilesPerHour => "mph",
+            Unit::Radians => "rad",
+            Unit::Degrees => "°",
+            Unit::Percent => "%",
+            Unit::Liters => "L",
+            Unit::Gallons => "gal",
+            Unit::Kilograms => "kg",
+            Unit::Pounds => "lb",
+            Unit::Seconds => "s",
+        }
+    }
+
+    /// Home Assistant sensor `device_class` matching the unit, if there is one.
+    /// Percentages and angles are ambiguous, so the caller has to pick a class for those.
+    pub fn device_class(&self) -> Option<&'static str> {
+        match self {
+            Unit::Kilometers
+            | Unit::Miles
+            | Unit::Meters
+            | Unit::Feet
+            | Unit::Millimeters
+            | Unit::Inches => Some("distance"),
+            Unit::Celsius | Unit::Fahrenheit => Some("temperature"),
+            Unit::InchesOfMercury | Unit::Hectopascals => Some("atmospheric_pressure"),
+            Unit::Kilopascals | Unit::Psi => Some("pressure"),
+            Unit::MetersPerSecond | Unit::KilometersPerHour | Unit::MilesPerHour => Some("speed"),
+            Unit::Liters | Unit::Gallons => Some("volume_storage"),
+            Unit::Kilograms | Unit::Pounds => Some("weight"),
+            Unit::Seconds => Some("duration"),
+            Unit::Radians | Unit::Degrees | Unit::Percent => None,
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let unit = match s {
+            "km" => Unit::Kilometers,
+            "mi" => Unit::Miles,
+            "m" => Unit::Meters,
+            "ft" => Unit::Feet,
+            "mm" => Unit::Millimeters,
+            "in" => Unit::Inches,
+            "C" => Unit::Celsius,
+            "F" => Unit::Fahrenheit,
+            "Hg" | "inHg" => Unit::InchesOfMercury,
+            "hPa" | "mbar" => Unit::Hectopascals,
+            "kPa" => Unit::Kilopascals,
+            "psi" => Unit::Psi,
+            "m/s" => Unit::MetersPerSecond,
+            "kph" | "km/h" => Unit::KilometersPerHour,
+            "mph" => Unit::MilesPerHour,
+            "rad" => Unit::Radians,
+            "deg" => Unit::Degrees,
+            "%" => Unit::Percent,
+            "l" | "L" => Unit::Liters,
+            "gal" => Unit::Gallons,
+            "kg" => Unit::Kilograms,
+            "lb" | "lbs" => Unit::Pounds,
+            "s" | "sec" => Unit::Seconds,
+            unknown => return Err(anyhow!("Unknown unit \"{unknown}\"")),
+        };
+        Ok(unit)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+
+    /// Convert to the matching unit in the given unit system.
+    /// Units without a counterpart in the other system are returned unchanged.
+    pub fn to_system(self, system: UnitSystem) -> Self {
+        let (value, unit) = match (system, self.unit) {
+            (UnitSystem::Metric, Unit::Miles) => (self.value * 1.609344, Unit::Kilometers),
+            (UnitSystem::Metric, Unit::Feet) => (self.value * 0.3048, Unit::Meters),
+            (UnitSystem::Metric, Unit::Inches) => (self.value * 25.4, Unit::Millimeters),
+            (UnitSystem::Metric, Unit::Fahrenheit) => {
+                ((self.value - 32.0) * 5.0 / 9.0, Unit::Celsius)
+            }
+            (UnitSystem::Metric, Unit::InchesOfMercury) => {
+                (self.value * 33.863886, Unit::Hectopascals)
+            }
+            (UnitSystem::Metric, Unit::Psi) => (self.value * 6.894757, Unit::Kilopascals),
+            (UnitSystem::Metric, Unit::MilesPerHour) => {
+                (self.value * 1.609344, Unit::KilometersPerHour)
+            }
+            (UnitSystem::Metric, Unit::Gallons) => (self.value * 3.785412, Unit::Liters),
+            (UnitSystem::Metric, Unit::Pounds) => (self.value * 0.453592, Unit::Kilograms),
+            (UnitSystem::Imperial, Unit::Kilometers) => (self.value / 1.609344, Unit::Miles),
+            (UnitSystem::Imperial, Unit::Meters) => (self.value / 0.3048, Unit::Feet),
+            (UnitSystem::Imperial, Unit::Millimeters) => (self.value / 25.4, Unit::Inches),
+            (UnitSystem::Imperial, Unit::Celsius) => {
+                (self.value * 9.0 / 5.0 + 32.0, Unit::Fahrenheit)
+            }
+            (UnitSystem::Imperial, Unit::Hectopascals) => {
+                (self.value / 33.863886, Unit::InchesOfMercury)
+            }
+            (UnitSystem::Imperial, Unit::Kilopascals) => (self.value / 6.894757, Unit::Psi),
+            (UnitSystem::Imperial, Unit::MetersPerSecond) => {
+                (self.value * 2.236936, Unit::MilesPerHour)
+            }
+            (UnitSystem::Imperial, Unit::KilometersPerHour) => {
+                (self.value / 1.609344, Unit::MilesPerHour)
+            }
+            (UnitSystem::Imperial, Unit::Liters) => (self.value / 3.785412, Unit::Gallons),
+            (UnitSystem::Imperial, Unit::Kilograms) => (self.value / 0.453592, Unit::Pounds),
+            (_, unit) => (self.value, unit),
+        };
+        Self { value, unit }
+    }
+
+    /// Convert to a specific unit of the same kind, like m/s to km/h.
+    /// Returns `None` for units that measure different things.
+    pub fn convert(self, unit: Unit) -> Option<Self> {
+        if self.unit == unit {
+            return Some(self);
+        }
+        let value = match (self.unit, unit) {
+            (Unit::Celsius, Unit::Fahrenheit) => self.value * 9.0 / 5.0 + 32.0,
+            (Unit::Fahrenheit, Unit::Celsius) => (self.value - 32.0) * 5.0 / 9.0,
+            (from, to) => {
+                let (from_kind, from_factor) = base_unit(from)?;
+                let (to_kind, to_factor) = base_unit(to)?;
+                if from_kind != to_kind {
+                    return None;
+                }
+                self.value * from_factor / to_factor
+            }
+        };
+        Some(Self { value, unit })
+    }
+}
+
+// The base unit of each kind of quantity, and the factor to convert to it
+fn base_unit(unit: Unit) -> Option<(Unit, f64)> {
+    let base = match unit {
+        Unit::Kilometers => (Unit::Meters, 1000.0),
+        Unit::Miles => (Unit::Meters, 1609.344),
+        Unit::Meters => (Unit::Meters, 1.0),
+        Unit::Feet => (Unit::Meters, 0.3048),
+        Unit::Millimeters => (Unit::Meters, 0.001),
+        Unit::Inches => (Unit::Meters, 0.0254),
+        Unit::InchesOfMercury => (Unit::Hectopascals, 33.863886),
+        Unit::Hectopascals => (Unit::Hectopascals, 1.0),
+        Unit::Kilopascals => (Unit::Hectopascals, 10.0),
+        Unit::Psi => (Unit::Hectopascals, 68.94757),
+        Unit::MetersPerSecond => (Unit::MetersPerSecond, 1.0),
+        Unit::KilometersPerHour => (Unit::MetersPerSecond, 1.0 / 3.6),
+        Unit::MilesPerHour => (Unit::MetersPerSecond, 0.44704),
+        Unit::Radians => (Unit::Radians, 1.0),
+        Unit::Degrees => (Unit::Radians, std::f64::consts::PI / 180.0),
+        Unit::Percent => (Unit::Percent, 1.0),
+        Unit::Liters => (Unit::Liters, 1.0),
+        Unit::Gallons => (Unit::Liters, 3.785412),
+        Unit::Kilograms => (Unit::Kilograms, 1.0),
+        Unit::Pounds => (Unit::Kilograms, 0.453592),
+        Unit::Seconds => (Unit::Seconds, 1.0),
+        Unit::Celsius | Unit::Fahrenheit => return None,
+    };
+    Some(base)
+}
+
+impl FromStr for Quantity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (value, unit) = s
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("Missing unit in \"{s}\""))?;
+        let value = value
+            .parse::<f64>()
+            .with_context(|| format!("Invalid value in \"{s}\""))?;
+        let unit = unit.trim().parse::<Unit>()?;
+        Ok(Self { value, unit })
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{} {}", self.value, self.unit.unit_of_measurement())
+    }
+}
+
+// Home Assistant gets the unit from the discovery config, so the state only carries the value
+impl Serialize for Quantity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.value)
+    }
+}
+
+/// Deserialize an optional quantity string, ignoring values we are unable to parse,
+/// so that a single odd value does not invalidate the whole session info
+pub fn deserialize_quantity<'de, D>(deserializer: D) -> Result<Option<Quantity>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| match value.parse::<Quantity>() {
+        Ok(quantity) => Some(quantity),
+        Err(e) => {
+            log::warn!("Failed to parse quantity: {e:?}");
+            None
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_quantities() {
+        let cases = [
+            ("0.72 km", 0.72, Unit::Kilometers),
+            ("26.11 C", 26.11, Unit::Celsius),
+            ("28.91 Hg", 28.91, Unit::InchesOfMercury),
+            ("0.89 m/s", 0.89, Unit::MetersPerSecond),
+            ("0.52 rad", 0.52, Unit::Radians),
+            ("72.00 kph", 72.0, Unit::KilometersPerHour),
+            ("45 %", 45.0, Unit::Percent),
+            ("-83.684817 m", -83.684817, Unit::Meters),
+            ("+0.1 mm", 0.1, Unit::Millimeters),
+        ];
+        for (input, value, unit) in cases {
+            assert_eq!(
+                input.parse::<Quantity>().unwrap(),
+                Quantity::new(value, unit)
+            );
+        }
+
+        assert!("unlimited".parse::<Quantity>().is_err());
+        assert!("12 furlongs".parse::<Quantity>().is_err());
+    }
+
+    #[test]
+    fn convert_between_unit_systems() {
+        let temp = Quantity::new(26.0, Unit::Celsius).to_system(UnitSystem::Imperial);
+        assert_eq!(temp.unit, Unit::Fahrenheit);
+        assert!((temp.value - 78.8).abs() < 1e-9);
+
+        let pressure = Quantity::new(28.91, Unit::InchesOfMercury).to_system(UnitSystem::Metric);
+        assert_eq!(pressure.unit, Unit::Hectopascals);
+        assert!((pressure.value - 979.0).abs() < 0.1);
+
+        let speed = Quantity::new(72.0, Unit::KilometersPerHour).to_system(UnitSystem::Metric);
+        assert_eq!(speed, Quantity::new(72.0, Unit::KilometersPerHour));
+
+        let angle = Quantity::new(0.52, Unit::Radians).to_system(UnitSystem::Imperial);
+        assert_eq!(angle, Quantity::new(0.52, Unit::Radians));
+    }
+
+    #[test]
+    fn convert_to_unit() {
+        let speed = Quantity::new(10.0, Unit::MetersPerSecond).convert(Unit::KilometersPerHour);
+        assert!((speed.unwrap().value - 36.0).abs() < 1e-9);
+
+        let angle = Quantity::new(0.5, Unit::Radians).convert(Unit::Degrees);
+        assert!((angle.unwrap().value - 28.647889).abs() < 1e-6);
+
+        let temp = Quantity::new(-40.0, Unit::Fahrenheit).convert(Unit::Celsius);
+        assert_eq!(temp, Some(Quantity::new(-40.0, Unit::Celsius)));
+
+        assert_eq!(
+            Quantity::new(1.0, Unit::Meters).convert(Unit::Seconds),
+            None
+        );
+    }
+}